@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 pub fn find_marker_of_len(len: usize, signal: &str) -> Option<usize> {
     signal
         .as_bytes()
@@ -12,6 +14,54 @@ pub fn find_marker_of_len(len: usize, signal: &str) -> Option<usize> {
         })
 }
 
+/// Like `find_marker_of_len`, but tolerates up to `max_duplicates` repeated symbols in the
+/// window and works on unicode scalar values. Positions are counted in `char`s.
+pub fn find_marker_with_duplicates(
+    len: usize,
+    max_duplicates: usize,
+    signal: &str,
+) -> Option<usize> {
+    markers_with_duplicates(len, max_duplicates, signal).next()
+}
+
+/// Every position after a window of `len` chars with at most `max_duplicates` repeated symbols.
+pub fn find_markers_with_duplicates(len: usize, max_duplicates: usize, signal: &str) -> Vec<usize> {
+    markers_with_duplicates(len, max_duplicates, signal).collect()
+}
+
+fn markers_with_duplicates(
+    len: usize,
+    max_duplicates: usize,
+    signal: &str,
+) -> impl Iterator<Item = usize> {
+    let chars = signal.chars().collect::<Vec<_>>();
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    // symbols in the window beyond their first occurrence
+    let mut duplicates = 0;
+
+    (0..chars.len()).filter_map(move |i| {
+        let count = counts.entry(chars[i]).or_insert(0);
+        if *count > 0 {
+            duplicates += 1;
+        }
+        *count += 1;
+
+        if i >= len {
+            let count = counts.get_mut(&chars[i - len]).unwrap();
+            *count -= 1;
+            if *count > 0 {
+                duplicates -= 1;
+            }
+        }
+
+        if i + 1 >= len && duplicates <= max_duplicates {
+            Some(i + 1)
+        } else {
+            None
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,4 +108,41 @@ mod tests {
             assert_eq!(find_marker_of_len(14, &signal).unwrap(), 3645);
         }
     }
+
+    mod fault_tolerant {
+        use super::*;
+        use std::fs::read_to_string;
+        use test_case::test_case;
+
+        #[test_case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4)]
+        #[test_case("bvwbjplbgvbhsrlpgdmjqwftvncz", 4)]
+        #[test_case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4)]
+        fn one_duplicate_works(signal: &str, pos: usize) {
+            assert_eq!(find_marker_with_duplicates(4, 1, signal).unwrap(), pos);
+        }
+
+        #[test_case("aabbcc", 4, 2, vec![4, 5, 6])]
+        #[test_case("aaaa", 3, 1, vec![])]
+        #[test_case("αβαγ", 3, 0, vec![4])]
+        #[test_case("日本日本語", 2, 0, vec![2, 3, 4, 5])]
+        fn all_markers_work(
+            signal: &str,
+            len: usize,
+            max_duplicates: usize,
+            positions: Vec<usize>,
+        ) {
+            assert_eq!(
+                find_markers_with_duplicates(len, max_duplicates, signal),
+                positions
+            );
+        }
+
+        #[test]
+        fn no_duplicates_matches_exact_search() {
+            let signal = read_to_string("input").unwrap();
+
+            assert_eq!(find_marker_with_duplicates(4, 0, &signal).unwrap(), 1080);
+            assert_eq!(find_marker_with_duplicates(14, 0, &signal).unwrap(), 3645);
+        }
+    }
 }
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use crate::{fs::FileSystem, parse_output};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Resized,
+}
+
+/// A file or directory that differs between two snapshots. Directory sizes are totals, so
+/// their delta already includes everything below them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub is_dir: bool,
+    pub kind: ChangeKind,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+impl Change {
+    pub fn delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+}
+
+/// Every added, removed and resized entry, ordered by path.
+pub fn diff(old: &FileSystem, new: &FileSystem) -> Vec<Change> {
+    let entries = |fs: &FileSystem| {
+        fs.walk()
+            .into_iter()
+            .map(|e| (e.path, (e.is_dir, e.size)))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let old = entries(old);
+    let mut new = entries(new);
+
+    let mut changes = vec![];
+    for (path, (is_dir, old_size)) in old {
+        let change = |kind, old_size, new_size| Change {
+            path: path.clone(),
+            is_dir,
+            kind,
+            old_size,
+            new_size,
+        };
+        match new.remove(&path) {
+            Some((new_is_dir, new_size)) if new_is_dir == is_dir => {
+                if new_size != old_size {
+                    changes.push(change(ChangeKind::Resized, old_size, new_size));
+                }
+            }
+            Some(replacement) => {
+                changes.push(change(ChangeKind::Removed, old_size, 0));
+                new.insert(path, replacement);
+            }
+            None => changes.push(change(ChangeKind::Removed, old_size, 0)),
+        }
+    }
+    changes.extend(new.into_iter().map(|(path, (is_dir, new_size))| Change {
+        path,
+        is_dir,
+        kind: ChangeKind::Added,
+        old_size: 0,
+        new_size,
+    }));
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+pub fn diff_transcripts(old: &str, new: &str) -> Result<Vec<Change>, String> {
+    let old = FileSystem::from_ops(&parse_output(old))?;
+    let new = FileSystem::from_ops(&parse_output(new))?;
+    Ok(diff(&old, &new))
+}
+
+/// Directories whose total changed, the ones that grew the most first.
+pub fn dir_deltas(changes: &[Change]) -> Vec<(&str, i64)> {
+    let mut deltas = changes
+        .iter()
+        .filter(|c| c.is_dir)
+        .map(|c| (c.path.as_str(), c.delta()))
+        .collect::<Vec<_>>();
+    deltas.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../example");
+
+    fn summary(changes: &[Change]) -> Vec<(&str, ChangeKind, i64)> {
+        changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.kind, c.delta()))
+            .collect()
+    }
+
+    #[test]
+    fn same_snapshot_has_no_changes() {
+        assert_eq!(diff_transcripts(EXAMPLE, EXAMPLE).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn changes_are_found() {
+        let new = EXAMPLE
+            .replace("584 i", "600 i")
+            .replace("4060174 j\n", "")
+            .replace("dir e\n", "dir e\ndir n\n")
+            + "$ cd /\n$ cd a\n$ cd n\n$ ls\n10 m\n";
+
+        let changes = diff_transcripts(EXAMPLE, &new).unwrap();
+        assert_eq!(
+            summary(&changes),
+            vec![
+                ("/", ChangeKind::Resized, 16 + 10 - 4060174),
+                ("/a", ChangeKind::Resized, 16 + 10),
+                ("/a/e", ChangeKind::Resized, 16),
+                ("/a/e/i", ChangeKind::Resized, 16),
+                ("/a/n", ChangeKind::Added, 10),
+                ("/a/n/m", ChangeKind::Added, 10),
+                ("/d", ChangeKind::Resized, -4060174),
+                ("/d/j", ChangeKind::Removed, -4060174),
+            ]
+        );
+        assert_eq!(
+            dir_deltas(&changes),
+            vec![
+                ("/a", 26),
+                ("/a/e", 16),
+                ("/a/n", 10),
+                ("/", 26 - 4060174),
+                ("/d", -4060174),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_replaced_by_directory() {
+        let old = "$ cd /\n$ ls\n5 a";
+        let new = "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n5 b";
+
+        let changes = diff_transcripts(old, new).unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|c| (c.path.as_str(), c.is_dir, c.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/a", false, ChangeKind::Removed),
+                ("/a", true, ChangeKind::Added),
+                ("/a/b", false, ChangeKind::Added),
+            ]
+        );
+    }
+}
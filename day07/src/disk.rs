@@ -0,0 +1,172 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+};
+
+use crate::fs::{check_name, FileSystem, NodeId, NodeKind, ROOT};
+
+/// Recreates the tree under `target`; files are sparse, only their length is set.
+pub fn materialise(fs: &FileSystem, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    materialise_dir(fs, ROOT, target)
+}
+
+/// `name` as a single path component, so joining it can never leave the target directory.
+fn component(name: &str) -> io::Result<&Path> {
+    check_name(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Ok(Path::new(name))
+}
+
+fn materialise_dir(fs: &FileSystem, id: NodeId, target: &Path) -> io::Result<()> {
+    for child in fs.children(id) {
+        let node = fs.node(child);
+        let path = target.join(component(&node.name)?);
+        match node.kind {
+            NodeKind::Dir(_) => {
+                fs::create_dir_all(&path)?;
+                materialise_dir(fs, child, &path)?;
+            }
+            NodeKind::File(size) => File::create(&path)?.set_len(size)?,
+        }
+    }
+    Ok(())
+}
+
+/// Walks a real directory and writes the `$ cd`/`$ ls` session that would explore it, in the
+/// same shape as the puzzle input. Symlinks and other special files are skipped.
+pub fn transcript_from_dir(root: &Path) -> io::Result<String> {
+    let mut out = String::from("$ cd /\n");
+    transcript_dir(root, &mut out)?;
+    Ok(out)
+}
+
+fn transcript_dir(dir: &Path, out: &mut String) -> io::Result<()> {
+    let mut dirs = vec![];
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|name| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{name:?} is not utf-8"))
+        })?;
+        // either would be read back as the start of a new line or command
+        for separator in ["\n", "$ "] {
+            if name.contains(separator) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{name:?} contains {separator:?}"),
+                ));
+            }
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            entries.push((name.clone(), format!("dir {name}")));
+            dirs.push((name, entry.path()));
+        } else if file_type.is_file() {
+            let size = entry.metadata()?.len();
+            entries.push((name.clone(), format!("{size} {name}")));
+        }
+    }
+    entries.sort();
+    dirs.sort();
+
+    out.push_str("$ ls\n");
+    for (_, line) in entries {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    for (name, path) in dirs {
+        out.push_str(&format!("$ cd {name}\n"));
+        transcript_dir(&path, out)?;
+        out.push_str("$ cd ..\n");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_output;
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("day07-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entries(fs: &FileSystem) -> Vec<(String, bool, u64)> {
+        fs.walk()
+            .into_iter()
+            .map(|e| (e.path, e.is_dir, e.size))
+            .collect()
+    }
+
+    fn round_trip(name: &str, input: &str) {
+        let dir = scratch_dir(name);
+        let fs = FileSystem::from_ops(&parse_output(input)).unwrap();
+
+        materialise(&fs, &dir).unwrap();
+        let transcript = transcript_from_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let copy = FileSystem::from_ops(&parse_output(&transcript)).unwrap();
+        assert_eq!(entries(&copy), entries(&fs));
+    }
+
+    #[test]
+    fn example_round_trips() {
+        round_trip("example", include_str!("../example"));
+    }
+
+    #[test]
+    fn input_round_trips() {
+        round_trip("input", &std::fs::read_to_string("input").unwrap());
+    }
+
+    #[test]
+    fn large_files_round_trip() {
+        round_trip("large", "$ cd /\n$ ls\n5000000000 big\n");
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("." ; "current")]
+    #[test_case(".." ; "parent")]
+    #[test_case("../escaped" ; "parent in name")]
+    #[test_case("/abs/path" ; "absolute")]
+    #[test_case("a\0b" ; "nul")]
+    fn unsafe_names_fail(name: &str) {
+        let err = component(name).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test_case("newline", "a\nb")]
+    #[test_case("prompt", "a$ b")]
+    fn unreadable_names_fail(case: &str, name: &str) {
+        let dir = scratch_dir(case);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), "").unwrap();
+
+        let err = transcript_from_dir(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn transcript_works() {
+        let dir = scratch_dir("transcript");
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        fs::write(dir.join("a"), "hello").unwrap();
+        fs::write(dir.join("b/c/d e"), "!").unwrap();
+
+        let transcript = transcript_from_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            transcript,
+            "$ cd /\n$ ls\n5 a\ndir b\n$ cd b\n$ ls\ndir c\n$ cd c\n$ ls\n1 d e\n$ cd ..\n$ cd ..\n"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+
+use crate::{Cd, Ls, Operation};
+
+pub type NodeId = usize;
+
+pub const ROOT: NodeId = 0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    Dir(BTreeMap<String, NodeId>),
+    File(u64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub kind: NodeKind,
+}
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir(_))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalkEntry {
+    pub id: NodeId,
+    pub path: String,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// Rejects names that would not stay a single path component: empty, `.`, `..`, or containing
+/// `/` or NUL.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if matches!(name, "" | "." | "..") || name.contains(['/', '\0']) {
+        return Err(format!("Invalid name {name:?}"));
+    }
+    Ok(())
+}
+
+/// Inode-like model of the filesystem explored by a transcript. Nodes live in an arena and
+/// refer to each other by `NodeId`; the root is always `ROOT` and is named `/`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        FileSystem {
+            nodes: vec![Node {
+                name: "/".to_string(),
+                parent: None,
+                kind: NodeKind::Dir(BTreeMap::new()),
+            }],
+        }
+    }
+
+    /// Replays the operations, creating directories as they are listed or entered.
+    pub fn from_ops(ops: &[Operation]) -> Result<Self, String> {
+        let mut fs = FileSystem::new();
+        let mut cwd = ROOT;
+        for op in ops {
+            match op {
+                Operation::Cd(Cd::Up) => cwd = fs.nodes[cwd].parent.unwrap_or(ROOT),
+                Operation::Cd(Cd::Down(path)) => cwd = fs.change_dir(cwd, path)?,
+                Operation::Ls(items) => {
+                    for item in items {
+                        match item {
+                            Ls::Dir(name) => fs.mkdir(cwd, name)?,
+                            Ls::File(name, size) => fs.touch(cwd, name, *size)?,
+                        };
+                    }
+                }
+            }
+        }
+        Ok(fs)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    /// Number of files and directories, not counting the root.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let children = match &self.nodes[id].kind {
+            NodeKind::Dir(children) => Some(children.values().copied()),
+            NodeKind::File(_) => None,
+        };
+        children.into_iter().flatten()
+    }
+
+    /// Returns the existing directory `name` in `parent` or creates it.
+    pub fn mkdir(&mut self, parent: NodeId, name: &str) -> Result<NodeId, String> {
+        match self.child(parent, name) {
+            Some(id) if self.nodes[id].is_dir() => Ok(id),
+            Some(_) => Err(format!("{} is a file", self.join(parent, name))),
+            None => self.insert(parent, name, NodeKind::Dir(BTreeMap::new())),
+        }
+    }
+
+    /// Creates the file `name` in `parent`, or updates the size of an existing one.
+    pub fn touch(&mut self, parent: NodeId, name: &str, size: u64) -> Result<NodeId, String> {
+        match self.child(parent, name) {
+            Some(id) => match &mut self.nodes[id].kind {
+                NodeKind::File(s) => {
+                    *s = size;
+                    Ok(id)
+                }
+                NodeKind::Dir(_) => Err(format!("{} is a directory", self.join(parent, name))),
+            },
+            None => self.insert(parent, name, NodeKind::File(size)),
+        }
+    }
+
+    fn insert(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> Result<NodeId, String> {
+        check_name(name)?;
+        let id = self.nodes.len();
+        match &mut self.nodes[parent].kind {
+            NodeKind::Dir(children) => children.insert(name.to_string(), id),
+            NodeKind::File(_) => return Err(format!("{} is not a directory", self.path(parent))),
+        };
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            kind,
+        });
+        Ok(id)
+    }
+
+    fn child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[parent].kind {
+            NodeKind::Dir(children) => children.get(name).copied(),
+            NodeKind::File(_) => None,
+        }
+    }
+
+    /// `cd` semantics: `/` goes to the root, `..` never leaves it and unknown names are created.
+    pub fn change_dir(&mut self, cwd: NodeId, path: &str) -> Result<NodeId, String> {
+        let mut current = if path.starts_with('/') { ROOT } else { cwd };
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            current = match segment {
+                "." => current,
+                ".." => self.nodes[current].parent.unwrap_or(ROOT),
+                name => self.mkdir(current, name)?,
+            };
+        }
+        Ok(current)
+    }
+
+    /// Resolves an absolute or relative `path` starting from `from`.
+    pub fn resolve(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let start = if path.starts_with('/') { ROOT } else { from };
+        path.split('/').filter(|s| !s.is_empty()).try_fold(
+            start,
+            |current, segment| match segment {
+                "." => Some(current),
+                ".." => Some(self.nodes[current].parent.unwrap_or(ROOT)),
+                name => self.child(current, name),
+            },
+        )
+    }
+
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        self.resolve(ROOT, path)
+    }
+
+    /// Absolute path of a node, `/` for the root.
+    pub fn path(&self, id: NodeId) -> String {
+        match self.nodes[id].parent {
+            None => "/".to_string(),
+            Some(parent) => self.join(parent, &self.nodes[id].name),
+        }
+    }
+
+    fn join(&self, parent: NodeId, name: &str) -> String {
+        match parent {
+            ROOT => format!("/{name}"),
+            _ => format!("{}/{name}", self.path(parent)),
+        }
+    }
+
+    /// Total size of every node, indexed by `NodeId`.
+    pub fn sizes(&self) -> Vec<u64> {
+        let mut sizes = vec![0; self.nodes.len()];
+        // children are always pushed after their parent
+        for id in (0..self.nodes.len()).rev() {
+            if let NodeKind::File(size) = self.nodes[id].kind {
+                sizes[id] = size;
+            }
+            if let Some(parent) = self.nodes[id].parent {
+                sizes[parent] += sizes[id];
+            }
+        }
+        sizes
+    }
+
+    pub fn size(&self, id: NodeId) -> u64 {
+        match &self.nodes[id].kind {
+            NodeKind::File(size) => *size,
+            NodeKind::Dir(children) => children.values().map(|&c| self.size(c)).sum(),
+        }
+    }
+
+    pub fn du(&self, path: &str) -> Option<u64> {
+        self.lookup(path).map(|id| self.size(id))
+    }
+
+    /// Every node in depth first order, children sorted by name, starting with the root.
+    pub fn walk(&self) -> Vec<WalkEntry> {
+        self.walk_from(ROOT)
+    }
+
+    pub fn walk_from(&self, id: NodeId) -> Vec<WalkEntry> {
+        let sizes = self.sizes();
+        let mut entries = vec![];
+        let mut stack = vec![(id, self.path(id), 0)];
+        while let Some((id, path, depth)) = stack.pop() {
+            let node = &self.nodes[id];
+            if let NodeKind::Dir(children) = &node.kind {
+                stack.extend(children.iter().rev().map(|(name, &child)| {
+                    let child_path = match id {
+                        ROOT => format!("/{name}"),
+                        _ => format!("{path}/{name}"),
+                    };
+                    (child, child_path, depth + 1)
+                }));
+            }
+            entries.push(WalkEntry {
+                id,
+                path,
+                depth,
+                is_dir: node.is_dir(),
+                size: sizes[id],
+            });
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_output;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = include_str!("../example");
+
+    #[test_case("/", Some(48381165))]
+    #[test_case("/a", Some(94853))]
+    #[test_case("/a/e", Some(584))]
+    #[test_case("/a/e/i", Some(584))]
+    #[test_case("/d", Some(24933642))]
+    #[test_case("/a/e/../../d/./j", Some(4060174))]
+    #[test_case("/..", Some(48381165) ; "parent of root")]
+    #[test_case("/x", None)]
+    fn du_works(path: &str, size: Option<u64>) {
+        let fs = FileSystem::from_ops(&parse_output(EXAMPLE)).unwrap();
+        assert_eq!(fs.du(path), size);
+    }
+
+    #[test]
+    fn cd_root_is_not_a_directory_named_slash() {
+        let fs = FileSystem::from_ops(&parse_output(EXAMPLE)).unwrap();
+        assert_eq!(fs.lookup("/"), Some(ROOT));
+        assert_eq!(fs.resolve(ROOT, "/"), Some(ROOT));
+        assert_eq!(
+            fs.children(ROOT)
+                .map(|id| fs.node(id).name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b.txt", "c.dat", "d"]
+        );
+    }
+
+    #[test]
+    fn listed_directories_are_kept() {
+        let ops = parse_output("$ cd /\n$ ls\ndir empty\n1 a");
+        let fs = FileSystem::from_ops(&ops).unwrap();
+        assert_eq!(fs.du("/empty"), Some(0));
+        assert!(fs.node(fs.lookup("/empty").unwrap()).is_dir());
+    }
+
+    #[test]
+    fn walk_works() {
+        let fs = FileSystem::from_ops(&parse_output(EXAMPLE)).unwrap();
+        let dirs = fs
+            .walk()
+            .into_iter()
+            .filter(|e| e.is_dir)
+            .map(|e| (e.path, e.depth, e.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dirs,
+            vec![
+                ("/".to_string(), 0, 48381165),
+                ("/a".to_string(), 1, 94853),
+                ("/a/e".to_string(), 2, 584),
+                ("/d".to_string(), 1, 24933642),
+            ]
+        );
+    }
+
+    #[test]
+    fn len_excludes_root() {
+        let fs = FileSystem::new();
+        assert_eq!((fs.len(), fs.is_empty()), (0, true));
+
+        let fs = FileSystem::from_ops(&parse_output(EXAMPLE)).unwrap();
+        assert_eq!((fs.len(), fs.is_empty()), (13, false));
+    }
+
+    #[test]
+    fn file_and_dir_names_conflict() {
+        let ops = parse_output("$ cd /\n$ ls\n1 a\n$ cd a");
+        assert!(FileSystem::from_ops(&ops).is_err());
+    }
+
+    #[test_case("$ cd /\n$ ls\n7 ../escaped" ; "parent in name")]
+    #[test_case("$ cd /\n$ ls\n5 /abs/path" ; "absolute name")]
+    #[test_case("$ cd /\n$ ls\ndir .." ; "parent")]
+    #[test_case("$ cd /\n$ ls\n1 ." ; "current")]
+    #[test_case("$ cd /\n$ ls\n1 a\0b" ; "nul")]
+    #[test_case("$ cd /\n$ ls\n1 " ; "empty")]
+    fn invalid_names_fail(input: &str) {
+        assert!(FileSystem::from_ops(&parse_output(input)).is_err());
+    }
+
+    #[test]
+    fn input_works() {
+        let input = std::fs::read_to_string("input").unwrap();
+        let fs = FileSystem::from_ops(&parse_output(&input)).unwrap();
+        let dirs = fs
+            .walk()
+            .into_iter()
+            .filter(|e| e.is_dir)
+            .collect::<Vec<_>>();
+
+        let under_100k: u64 = dirs.iter().map(|e| e.size).filter(|&s| s <= 100000).sum();
+        assert_eq!(under_100k, 1315285);
+
+        let needed = 30_000_000 - (70_000_000 - dirs[0].size);
+        let smallest = dirs.iter().map(|e| e.size).filter(|&s| s >= needed).min();
+        assert_eq!(smallest, Some(9847279));
+    }
+}
@@ -1,7 +1,18 @@
+pub mod diff;
+pub mod disk;
+pub mod fs;
+pub mod planner;
+pub mod query;
+pub mod render;
+pub mod validate;
+
+pub use fs::FileSystem;
+pub use validate::validate_output;
+
 #[derive(Debug)]
 pub enum TreeEntry<'a> {
     Dir(&'a str, Vec<TreeEntry<'a>>),
-    File(&'a str, u32),
+    File(&'a str, u64),
 }
 
 #[derive(Debug)]
@@ -12,7 +23,7 @@ pub enum Operation<'a> {
 
 #[derive(Debug)]
 pub enum Ls<'a> {
-    File(&'a str, u32),
+    File(&'a str, u64),
     Dir(&'a str),
 }
 
@@ -22,7 +33,7 @@ pub enum Cd<'a> {
     Down(&'a str),
 }
 
-pub fn parse_output(input: &str) -> Vec<Operation> {
+pub fn parse_output(input: &str) -> Vec<Operation<'_>> {
     input
         .split("$ ")
         .skip(1)
@@ -36,7 +47,7 @@ pub fn parse_output(input: &str) -> Vec<Operation> {
                         .iter()
                         .filter_map(|&line| match line.splitn(2, ' ').collect::<Vec<_>>()[..] {
                             ["dir", name] => Some(Ls::Dir(name)),
-                            [size, name] => Some(Ls::File(name, size.parse::<u32>().unwrap_or(0))),
+                            [size, name] => Some(Ls::File(name, size.parse::<u64>().unwrap_or(0))),
                             _ => None,
                         })
                         .collect(),
@@ -74,14 +85,14 @@ pub fn build_dir<'a>(ops: &mut Vec<&Operation<'a>>) -> Vec<TreeEntry<'a>> {
 }
 
 #[derive(Debug)]
-pub struct DirStats<'a>(&'a str, u32, Vec<DirStats<'a>>);
+pub struct DirStats<'a>(&'a str, u64, Vec<DirStats<'a>>);
 
-pub fn flatten_tree<'a>(tree: &[TreeEntry<'a>]) -> Vec<(&'a str, u32)> {
+pub fn flatten_tree<'a>(tree: &[TreeEntry<'a>]) -> Vec<(&'a str, u64)> {
     let DirStats(_name, _size, rest) = walk_tree("/", tree);
     rest.iter().flat_map(fl).collect()
 }
 
-fn fl<'a>(ds: &DirStats<'a>) -> Vec<(&'a str, u32)> {
+fn fl<'a>(ds: &DirStats<'a>) -> Vec<(&'a str, u64)> {
     let mut v = vec![(ds.0, ds.1)];
     v.append(&mut ds.2.iter().flat_map(fl).collect());
     v
@@ -109,7 +120,7 @@ mod tests {
         use super::*;
         use std::fs::read_to_string;
 
-        fn test_helper(input: &str) -> u32 {
+        fn test_helper(input: &str) -> u64 {
             let ops = parse_output(input);
             let tree = build_tree(&ops);
             let sizes = flatten_tree(&tree);
@@ -163,7 +174,7 @@ $ ls
 
         use super::*;
 
-        fn test_helper(input: &str, disk_size: u32, update_size: u32) -> Option<u32> {
+        fn test_helper(input: &str, disk_size: u64, update_size: u64) -> Option<u64> {
             let ops = parse_output(input);
             let tree = build_tree(&ops);
             let sizes = flatten_tree(&tree);
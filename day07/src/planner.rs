@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::fs::{FileSystem, NodeId, ROOT};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeletionPlan {
+    pub dirs: Vec<String>,
+    pub freed: u64,
+    /// `false` when the tree was too big for the exact search and the heuristic was used.
+    pub exact: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeletionPlanner {
+    /// Paths that must survive, along with every directory containing them.
+    /// Paths that do not exist are ignored.
+    pub protected: Vec<String>,
+    /// Maximum number of partial sums kept per directory before falling back to the heuristic.
+    pub exact_limit: usize,
+}
+
+impl Default for DeletionPlanner {
+    fn default() -> Self {
+        DeletionPlanner {
+            protected: vec![],
+            exact_limit: 1 << 12,
+        }
+    }
+}
+
+/// Bytes that still have to be freed before `update_size` fits on the disk.
+pub fn space_needed(fs: &FileSystem, disk_size: u64, update_size: u64) -> u64 {
+    let free = disk_size.saturating_sub(fs.size(ROOT));
+    update_size.saturating_sub(free)
+}
+
+// partial sums of deleted bytes, each with the directories producing it
+type Sums = BTreeMap<u64, Vec<NodeId>>;
+
+impl DeletionPlanner {
+    /// Picks non-nested directories freeing at least `needed` bytes while deleting as little as
+    /// possible. Returns `None` when even deleting everything allowed is not enough.
+    pub fn plan(&self, fs: &FileSystem, needed: u64) -> Option<DeletionPlan> {
+        let sizes = fs.sizes();
+        let blocked = self.blocked(fs);
+
+        let (dirs, exact) = match self.exact(fs, &sizes, &blocked, ROOT, needed) {
+            Some(sums) => (sums.range(needed..).next()?.1.clone(), true),
+            None => (self.heuristic(fs, &sizes, &blocked, needed)?, false),
+        };
+
+        Some(DeletionPlan {
+            freed: dirs.iter().map(|&id| sizes[id]).sum(),
+            dirs: dirs.into_iter().map(|id| fs.path(id)).collect(),
+            exact,
+        })
+    }
+
+    fn blocked(&self, fs: &FileSystem) -> HashSet<NodeId> {
+        let mut blocked = HashSet::new();
+        for path in self.protected.iter() {
+            let mut current = fs.lookup(path);
+            while let Some(id) = current {
+                blocked.insert(id);
+                current = fs.node(id).parent;
+            }
+        }
+        blocked
+    }
+
+    fn exact(
+        &self,
+        fs: &FileSystem,
+        sizes: &[u64],
+        blocked: &HashSet<NodeId>,
+        id: NodeId,
+        needed: u64,
+    ) -> Option<Sums> {
+        let mut sums = Sums::from([(0, vec![])]);
+        for child in fs.children(id).filter(|&c| fs.node(c).is_dir()) {
+            let child_sums = self.exact(fs, sizes, blocked, child, needed)?;
+            let mut combined = Sums::new();
+            for (a, dirs_a) in sums.iter() {
+                for (b, dirs_b) in child_sums.iter() {
+                    combined
+                        .entry(a + b)
+                        .or_insert_with(|| [&dirs_a[..], &dirs_b[..]].concat());
+                }
+            }
+            sums = prune(combined, needed);
+            if sums.len() > self.exact_limit {
+                return None;
+            }
+        }
+        if !blocked.contains(&id) {
+            sums.entry(sizes[id]).or_insert_with(|| vec![id]);
+            sums = prune(sums, needed);
+        }
+        Some(sums)
+    }
+
+    /// Best fit: note the smallest directory covering what is left, then commit to the largest
+    /// one that does not and repeat.
+    fn heuristic(
+        &self,
+        fs: &FileSystem,
+        sizes: &[u64],
+        blocked: &HashSet<NodeId>,
+        needed: u64,
+    ) -> Option<Vec<NodeId>> {
+        if needed == 0 {
+            return Some(vec![]);
+        }
+        let mut candidates = fs
+            .walk()
+            .into_iter()
+            .filter(|e| e.is_dir && !blocked.contains(&e.id))
+            .map(|e| e.id)
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|&id| sizes[id]);
+
+        let mut chosen: Vec<NodeId> = vec![];
+        let mut freed = 0;
+        let mut best: Option<(u64, Vec<NodeId>)> = None;
+        loop {
+            let remaining = needed - freed;
+            let free = candidates
+                .iter()
+                .copied()
+                .filter(|&c| chosen.iter().all(|&d| !nested(fs, c, d)))
+                .collect::<Vec<_>>();
+
+            if let Some(&fit) = free.iter().find(|&&c| sizes[c] >= remaining) {
+                let total = freed + sizes[fit];
+                if best.as_ref().is_none_or(|(b, _)| total < *b) {
+                    best = Some((total, [&chosen[..], &[fit]].concat()));
+                }
+            }
+            match free.iter().rev().find(|&&c| sizes[c] < remaining) {
+                Some(&largest) if sizes[largest] > 0 => {
+                    chosen.push(largest);
+                    freed += sizes[largest];
+                }
+                _ => break,
+            }
+        }
+        best.map(|(_, dirs)| dirs)
+    }
+}
+
+/// Keeps every sum below `needed` and only the smallest one reaching it.
+fn prune(mut sums: Sums, needed: u64) -> Sums {
+    let mut over = sums.split_off(&needed);
+    if let Some((sum, dirs)) = over.pop_first() {
+        sums.insert(sum, dirs);
+    }
+    sums
+}
+
+fn nested(fs: &FileSystem, a: NodeId, b: NodeId) -> bool {
+    is_ancestor(fs, a, b) || is_ancestor(fs, b, a)
+}
+
+fn is_ancestor(fs: &FileSystem, ancestor: NodeId, mut id: NodeId) -> bool {
+    loop {
+        if id == ancestor {
+            return true;
+        }
+        match fs.node(id).parent {
+            Some(parent) => id = parent,
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_output;
+
+    const SIBLINGS: &str = "$ cd /\n$ ls\ndir x\ndir y\ndir z\n\
+        $ cd x\n$ ls\n60 f\n$ cd ..\n$ cd y\n$ ls\n50 f\n$ cd ..\n$ cd z\n$ ls\n45 f";
+
+    fn fs(input: &str) -> FileSystem {
+        FileSystem::from_ops(&parse_output(input)).unwrap()
+    }
+
+    #[test]
+    fn example_works() {
+        let fs = fs(include_str!("../example"));
+        let needed = space_needed(&fs, 70_000_000, 30_000_000);
+        let plan = DeletionPlanner::default().plan(&fs, needed).unwrap();
+        assert_eq!(plan.dirs, vec!["/d"]);
+        assert_eq!(plan.freed, 24933642);
+        assert!(plan.exact);
+    }
+
+    #[test]
+    fn input_works() {
+        let fs = fs(&std::fs::read_to_string("input").unwrap());
+        let needed = space_needed(&fs, 70_000_000, 30_000_000);
+        let plan = DeletionPlanner::default().plan(&fs, needed).unwrap();
+        // too many directories for the exact search
+        assert!(!plan.exact);
+        assert!(plan.freed >= needed);
+        assert!(plan.freed <= 9847279);
+    }
+
+    #[test]
+    fn several_directories_beat_one() {
+        let plan = DeletionPlanner::default().plan(&fs(SIBLINGS), 95).unwrap();
+        assert_eq!(plan.dirs, vec!["/y", "/z"]);
+        assert_eq!(plan.freed, 95);
+    }
+
+    #[test]
+    fn protected_paths_are_kept() {
+        let planner = DeletionPlanner {
+            protected: vec!["/y/f".to_string()],
+            ..Default::default()
+        };
+        let plan = planner.plan(&fs(SIBLINGS), 95).unwrap();
+        assert_eq!(plan.dirs, vec!["/x", "/z"]);
+        assert_eq!(plan.freed, 105);
+
+        let planner = DeletionPlanner {
+            protected: vec!["/x".to_string(), "/y".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(planner.plan(&fs(SIBLINGS), 95), None);
+    }
+
+    #[test]
+    fn heuristic_is_used_past_the_limit() {
+        let planner = DeletionPlanner {
+            exact_limit: 0,
+            ..Default::default()
+        };
+        let plan = planner.plan(&fs(SIBLINGS), 95).unwrap();
+        assert!(!plan.exact);
+        assert_eq!(plan.dirs, vec!["/x", "/z"]);
+        assert_eq!(plan.freed, 105);
+
+        let plan = planner
+            .plan(&fs(include_str!("../example")), 8381165)
+            .unwrap();
+        assert_eq!(plan.dirs, vec!["/d"]);
+    }
+
+    #[test]
+    fn nothing_to_free() {
+        let plan = DeletionPlanner::default().plan(&fs(SIBLINGS), 0).unwrap();
+        assert!(plan.dirs.is_empty());
+        assert_eq!(plan.freed, 0);
+    }
+}
@@ -0,0 +1,316 @@
+use std::{iter::Peekable, str::FromStr, vec::IntoIter};
+
+use crate::fs::{FileSystem, WalkEntry};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    pub fn test<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Ge => left >= right,
+            Comparison::Gt => left > right,
+        }
+    }
+}
+
+/// A `find`-like filter over walked entries.
+///
+/// The text form combines `name=<glob>`, `path=<glob>`, `size<op><bytes>`, `depth<op><n>` and
+/// `type=dir|file` with `and`, `or`, `not` and parentheses, e.g. `size>100000 and name=*.log`.
+/// Globs support `*` and `?`; operators are `< <= = != >= >`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Name(String),
+    Path(String),
+    Size(Comparison, u64),
+    Depth(Comparison, usize),
+    IsDir(bool),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+impl Query {
+    pub fn matches(&self, fs: &FileSystem, entry: &WalkEntry) -> bool {
+        match self {
+            Query::Name(glob) => glob_match(glob, &fs.node(entry.id).name),
+            Query::Path(glob) => glob_match(glob, &entry.path),
+            Query::Size(cmp, size) => cmp.test(entry.size, *size),
+            Query::Depth(cmp, depth) => cmp.test(entry.depth, *depth),
+            Query::IsDir(is_dir) => entry.is_dir == *is_dir,
+            Query::And(a, b) => a.matches(fs, entry) && b.matches(fs, entry),
+            Query::Or(a, b) => a.matches(fs, entry) || b.matches(fs, entry),
+            Query::Not(q) => !q.matches(fs, entry),
+        }
+    }
+}
+
+/// Entries matching `query`, in walk order.
+pub fn find(fs: &FileSystem, query: &Query) -> Vec<WalkEntry> {
+    fs.walk()
+        .into_iter()
+        .filter(|e| query.matches(fs, e))
+        .collect()
+}
+
+fn glob_match(glob: &str, text: &str) -> bool {
+    let glob = glob.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut g, mut t) = (0, 0);
+    // position of the last `*` and the text position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, t));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sg, st)) => {
+                    star = Some((sg, st + 1));
+                    g = sg + 1;
+                    t = st + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Comparison),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Op(match (c, eq) {
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    ('=', _) => Comparison::Eq,
+                    ('!', true) => Comparison::Ne,
+                    _ => return Err("Expected = after !".to_string()),
+                }));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"()<>=!".contains(c))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+}
+
+impl Parser {
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|t| matches!(t, Token::Word(w) if w == keyword))
+            .is_some()
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.keyword("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.not()?;
+        while self.keyword("and") {
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+        Ok(query)
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        if self.keyword("not") {
+            Ok(Query::Not(Box::new(self.not()?)))
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Query, String> {
+        match self.tokens.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err("Expected )".to_string()),
+                }
+            }
+            Some(Token::Word(field)) => {
+                let cmp = match self.tokens.next() {
+                    Some(Token::Op(cmp)) => cmp,
+                    _ => return Err(format!("Expected a comparison after {field}")),
+                };
+                let value = match self.tokens.next() {
+                    Some(Token::Word(value)) => value,
+                    _ => return Err(format!("Expected a value after {field}")),
+                };
+                let number = || {
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid number {value}"))
+                };
+                match (field.as_str(), cmp) {
+                    ("name", Comparison::Eq) => Ok(Query::Name(value)),
+                    ("path", Comparison::Eq) => Ok(Query::Path(value)),
+                    ("name", Comparison::Ne) => Ok(Query::Not(Box::new(Query::Name(value)))),
+                    ("path", Comparison::Ne) => Ok(Query::Not(Box::new(Query::Path(value)))),
+                    ("size", cmp) => Ok(Query::Size(cmp, number()?)),
+                    ("depth", cmp) => Ok(Query::Depth(cmp, number()? as usize)),
+                    ("type", Comparison::Eq | Comparison::Ne) => {
+                        let is_dir = match value.as_str() {
+                            "dir" => true,
+                            "file" => false,
+                            _ => return Err(format!("Unknown type {value}")),
+                        };
+                        Ok(Query::IsDir(is_dir == (cmp == Comparison::Eq)))
+                    }
+                    _ => Err(format!("Unsupported filter {field}")),
+                }
+            }
+            Some(token) => Err(format!("Unexpected {token:?}")),
+            None => Err("Unexpected end of query".to_string()),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?.into_iter().peekable(),
+        };
+        let query = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(query),
+            Some(token) => Err(format!("Unexpected {token:?}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_output;
+    use test_case::test_case;
+
+    fn paths(input: &str, query: &str) -> Vec<String> {
+        let fs = FileSystem::from_ops(&parse_output(input)).unwrap();
+        find(&fs, &query.parse().unwrap())
+            .into_iter()
+            .map(|e| e.path)
+            .collect()
+    }
+
+    #[test_case("*.log", "d.log", true ; "log suffix")]
+    #[test_case("*.log", "d.ext", false ; "log suffix mismatch")]
+    #[test_case("d.*", "d.log", true ; "prefix")]
+    #[test_case("?", "j", true ; "single char")]
+    #[test_case("?", "/", true ; "root name")]
+    #[test_case("a*b*c", "aXbYbZc", true ; "several stars")]
+    #[test_case("a*b*c", "aXbYbZ", false ; "several stars mismatch")]
+    #[test_case("*", "", true ; "star on empty")]
+    #[test_case("", "x", false ; "empty glob")]
+    fn glob_works(glob: &str, text: &str, expected: bool) {
+        assert_eq!(glob_match(glob, text), expected);
+    }
+
+    #[test_case("size>100000 and name=*.log", &["/d/d.log"])]
+    #[test_case("type=dir and size<=100000", &["/a", "/a/e"])]
+    #[test_case("type=file and depth>=2 and size<1000", &["/a/e/i"])]
+    #[test_case("depth=1 and not type=dir", &["/b.txt", "/c.dat"])]
+    #[test_case("path=/d/* and (name=j or name=k)", &["/d/j", "/d/k"])]
+    #[test_case("name!=*.* and type!=dir and depth<2", &[])]
+    #[test_case("size>=2557 and size<=29116", &["/a/f", "/a/g"])]
+    fn queries_work(query: &str, expected: &[&str]) {
+        assert_eq!(paths(include_str!("../example"), query), expected);
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("size" ; "missing op")]
+    #[test_case("size>" ; "missing value")]
+    #[test_case("size>abc" ; "bad number")]
+    #[test_case("name<x" ; "bad name op")]
+    #[test_case("type=link" ; "bad type")]
+    #[test_case("colour=red" ; "bad field")]
+    #[test_case("(size>1" ; "unclosed")]
+    #[test_case("size>1 size<2" ; "missing and")]
+    #[test_case("size!1" ; "bad bang")]
+    fn invalid_queries_fail(query: &str) {
+        assert!(query.parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn precedence_works() {
+        assert_eq!(
+            "type=dir or size>1 and not depth<2"
+                .parse::<Query>()
+                .unwrap(),
+            Query::Or(
+                Box::new(Query::IsDir(true)),
+                Box::new(Query::And(
+                    Box::new(Query::Size(Comparison::Gt, 1)),
+                    Box::new(Query::Not(Box::new(Query::Depth(Comparison::Lt, 2))))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn input_works() {
+        let input = std::fs::read_to_string("input").unwrap();
+        let fs = FileSystem::from_ops(&parse_output(&input)).unwrap();
+        let query = "type=dir and size<=100000".parse().unwrap();
+        let total: u64 = find(&fs, &query).iter().map(|e| e.size).sum();
+        assert_eq!(total, 1315285);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::Write;
+
+use crate::fs::{FileSystem, NodeId, NodeKind, ROOT};
+
+/// `tree`-like view, directories end with `/` and every entry shows its total size.
+pub fn render_tree(fs: &FileSystem) -> String {
+    let sizes = fs.sizes();
+    let mut out = format!("/ ({})\n", sizes[ROOT]);
+    render_children(fs, &sizes, ROOT, "", &mut out);
+    out
+}
+
+fn render_children(fs: &FileSystem, sizes: &[u64], id: NodeId, prefix: &str, out: &mut String) {
+    let children = fs.children(id).collect::<Vec<_>>();
+    for (i, &child) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        let node = fs.node(child);
+        let slash = if node.is_dir() { "/" } else { "" };
+        let branch = if last { "└── " } else { "├── " };
+        writeln!(
+            out,
+            "{prefix}{branch}{}{slash} ({})",
+            node.name, sizes[child]
+        )
+        .unwrap();
+
+        if node.is_dir() {
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            render_children(fs, sizes, child, &prefix, out);
+        }
+    }
+}
+
+/// `du -h | sort -hr`-like listing of every directory.
+pub fn render_du(fs: &FileSystem) -> String {
+    let mut dirs = fs
+        .walk()
+        .into_iter()
+        .filter(|e| e.is_dir)
+        .collect::<Vec<_>>();
+    dirs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    dirs.iter()
+        .map(|e| format!("{}\t{}\n", human_size(e.size), e.path))
+        .collect()
+}
+
+/// Sizes the way `du -h` prints them: powers of 1024, rounded up, one decimal below 10.
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    loop {
+        let rounded = if value < 10.0 {
+            (value * 10.0).ceil() / 10.0
+        } else {
+            value.ceil()
+        };
+        if rounded < 1024.0 || unit == UNITS.len() - 1 {
+            return if rounded < 10.0 {
+                format!("{rounded:.1}{}", UNITS[unit])
+            } else {
+                format!("{rounded:.0}{}", UNITS[unit])
+            };
+        }
+        value /= 1024.0;
+        unit += 1;
+    }
+}
+
+/// Nested JSON objects with `name`, `type`, `size` and, for directories, `children`.
+pub fn render_json(fs: &FileSystem) -> String {
+    let sizes = fs.sizes();
+    let mut out = String::new();
+    render_json_node(fs, &sizes, ROOT, &mut out);
+    out
+}
+
+fn render_json_node(fs: &FileSystem, sizes: &[u64], id: NodeId, out: &mut String) {
+    let node = fs.node(id);
+    out.push_str("{\"name\":");
+    push_json_string(&node.name, out);
+    match &node.kind {
+        NodeKind::File(size) => write!(out, ",\"type\":\"file\",\"size\":{size}}}").unwrap(),
+        NodeKind::Dir(children) => {
+            write!(
+                out,
+                ",\"type\":\"dir\",\"size\":{},\"children\":[",
+                sizes[id]
+            )
+            .unwrap();
+            for (i, &child) in children.values().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                render_json_node(fs, sizes, child, out);
+            }
+            out.push_str("]}");
+        }
+    }
+}
+
+fn push_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_output;
+    use test_case::test_case;
+
+    fn example() -> FileSystem {
+        FileSystem::from_ops(&parse_output(include_str!("../example"))).unwrap()
+    }
+
+    #[test]
+    fn tree_works() {
+        assert_eq!(
+            render_tree(&example()),
+            r#"/ (48381165)
+├── a/ (94853)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d/ (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+"#
+        );
+    }
+
+    #[test]
+    fn du_works() {
+        assert_eq!(
+            render_du(&example()),
+            "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n"
+        );
+    }
+
+    #[test_case(0, "0")]
+    #[test_case(1023, "1023")]
+    #[test_case(1024, "1.0K")]
+    #[test_case(1025, "1.1K")]
+    #[test_case(10 * 1024, "10K")]
+    #[test_case(1024 * 1024 - 1, "1.0M")]
+    #[test_case(5 * 1024 * 1024 * 1024, "5.0G")]
+    fn human_size_works(size: u64, expected: &str) {
+        assert_eq!(human_size(size), expected);
+    }
+
+    #[test]
+    fn json_works() {
+        let ops = parse_output("$ cd /\n$ ls\ndir a\"b\n3 c\n$ cd a\"b\n$ ls\n4 d");
+        let fs = FileSystem::from_ops(&ops).unwrap();
+        assert_eq!(
+            render_json(&fs),
+            concat!(
+                r#"{"name":"/","type":"dir","size":7,"children":["#,
+                r#"{"name":"a\"b","type":"dir","size":4,"children":["#,
+                r#"{"name":"d","type":"file","size":4}]},"#,
+                r#"{"name":"c","type":"file","size":3}]}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, collections::HashMap, fmt};
+
+use crate::fs::{check_name, FileSystem, NodeId, ROOT};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    UnlistedDirectory(String),
+    NotADirectory(String),
+    AboveRoot,
+    ListingMismatch(String),
+    SizeConflict {
+        path: String,
+        first: u64,
+        second: u64,
+    },
+    InvalidSize(String),
+    InvalidEntry(String),
+    UnknownCommand(String),
+    UnexpectedOutput(String),
+}
+
+/// An issue found in a transcript, `line` is 1-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub line: usize,
+    pub issue: Issue,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.issue {
+            Issue::UnlistedDirectory(path) => write!(f, "cd into unlisted directory {path}"),
+            Issue::NotADirectory(path) => write!(f, "cd into file {path}"),
+            Issue::AboveRoot => write!(f, "cd .. above the root"),
+            Issue::ListingMismatch(path) => write!(f, "{path} listed with different contents"),
+            Issue::SizeConflict {
+                path,
+                first,
+                second,
+            } => write!(f, "{path} listed with sizes {first} and {second}"),
+            Issue::InvalidSize(line) => write!(f, "invalid size in \"{line}\""),
+            Issue::InvalidEntry(line) => write!(f, "invalid ls entry \"{line}\""),
+            Issue::UnknownCommand(command) => write!(f, "unknown command \"{command}\""),
+            Issue::UnexpectedOutput(line) => write!(f, "output without a command \"{line}\""),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Entry {
+    Dir,
+    File(u64),
+}
+
+impl Entry {
+    fn is_dir(&self) -> bool {
+        matches!(self, Entry::Dir)
+    }
+}
+
+#[derive(Default)]
+struct Listing {
+    line: usize,
+    entries: BTreeMap<String, (usize, Entry)>,
+}
+
+struct Validator {
+    fs: FileSystem,
+    cwd: NodeId,
+    listings: HashMap<NodeId, Listing>,
+    current: Option<(NodeId, Listing)>,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn report(&mut self, line: usize, issue: Issue) {
+        self.errors.push(ValidationError { line, issue });
+    }
+
+    fn command(&mut self, line: usize, command: &str) {
+        self.finish_listing();
+        match command.split(' ').collect::<Vec<_>>()[..] {
+            ["ls"] => {
+                self.current = Some((
+                    self.cwd,
+                    Listing {
+                        line,
+                        ..Default::default()
+                    },
+                ))
+            }
+            ["cd", "/"] => self.cwd = ROOT,
+            ["cd", ".."] => match self.fs.node(self.cwd).parent {
+                Some(parent) => self.cwd = parent,
+                None => self.report(line, Issue::AboveRoot),
+            },
+            ["cd", path] if !path.is_empty() => self.change_dir(line, path),
+            _ => self.report(line, Issue::UnknownCommand(command.to_string())),
+        }
+    }
+
+    /// Follows `path` one segment at a time like `FileSystem::resolve`, reporting the first
+    /// segment that is not a listed directory.
+    fn change_dir(&mut self, line: usize, path: &str) {
+        let mut current = if path.starts_with('/') {
+            ROOT
+        } else {
+            self.cwd
+        };
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            current = match segment {
+                "." => current,
+                ".." => self.fs.node(current).parent.unwrap_or(ROOT),
+                name => match self.fs.resolve(current, name) {
+                    Some(id) if self.fs.node(id).is_dir() => id,
+                    Some(id) => {
+                        self.report(line, Issue::NotADirectory(self.fs.path(id)));
+                        return;
+                    }
+                    None => {
+                        let parent = self.fs.path(current);
+                        let missing = format!("{}/{name}", parent.trim_end_matches('/'));
+                        self.report(line, Issue::UnlistedDirectory(missing));
+                        // keep going so the rest of the transcript is still checked, staying
+                        // put when the path cannot be created either
+                        if let Ok(id) = self.fs.change_dir(self.cwd, path) {
+                            self.cwd = id;
+                        }
+                        return;
+                    }
+                },
+            };
+        }
+        self.cwd = current;
+    }
+
+    fn output(&mut self, line: usize, text: &str) {
+        let entry = match text.split_once(' ') {
+            Some(("dir", name)) => Some((name, Entry::Dir)),
+            Some((size, name)) => match size.parse::<u64>() {
+                Ok(size) => Some((name, Entry::File(size))),
+                Err(_) => {
+                    self.report(line, Issue::InvalidSize(text.to_string()));
+                    None
+                }
+            },
+            None => {
+                self.report(line, Issue::InvalidEntry(text.to_string()));
+                None
+            }
+        };
+        let Some((name, entry)) = entry else {
+            return;
+        };
+        if check_name(name).is_err() {
+            self.report(line, Issue::InvalidEntry(text.to_string()));
+            return;
+        }
+
+        match self.current.as_mut() {
+            None => self.errors.push(ValidationError {
+                line,
+                issue: Issue::UnexpectedOutput(text.to_string()),
+            }),
+            Some((dir, listing)) => match listing.entries.get(name) {
+                Some(&(_, previous)) if previous != entry => {
+                    let path = self.fs.path(*dir);
+                    let path = format!("{}/{name}", path.trim_end_matches('/'));
+                    let issue = match (previous, entry) {
+                        (Entry::File(first), Entry::File(second)) => Issue::SizeConflict {
+                            path,
+                            first,
+                            second,
+                        },
+                        _ => Issue::ListingMismatch(self.fs.path(*dir)),
+                    };
+                    self.errors.push(ValidationError { line, issue });
+                }
+                Some(_) => {}
+                None => {
+                    listing.entries.insert(name.to_string(), (line, entry));
+                }
+            },
+        }
+    }
+
+    fn finish_listing(&mut self) {
+        let Some((dir, listing)) = self.current.take() else {
+            return;
+        };
+
+        match self.listings.get(&dir) {
+            Some(previous) => {
+                let same_shape = previous.entries.len() == listing.entries.len()
+                    && listing.entries.iter().all(|(name, (_, entry))| {
+                        previous.entries.get(name).map(|(_, e)| e.is_dir()) == Some(entry.is_dir())
+                    });
+                if !same_shape {
+                    let issue = Issue::ListingMismatch(self.fs.path(dir));
+                    self.errors.push(ValidationError {
+                        line: listing.line,
+                        issue,
+                    });
+                }
+                for (name, &(line, entry)) in listing.entries.iter() {
+                    if let (Some(&(_, Entry::File(first))), Entry::File(second)) =
+                        (previous.entries.get(name), entry)
+                    {
+                        if first != second {
+                            let path = self.fs.path(dir);
+                            let path = format!("{}/{name}", path.trim_end_matches('/'));
+                            self.errors.push(ValidationError {
+                                line,
+                                issue: Issue::SizeConflict {
+                                    path,
+                                    first,
+                                    second,
+                                },
+                            });
+                        }
+                    }
+                }
+            }
+            None => {
+                for (name, &(line, entry)) in listing.entries.iter() {
+                    let result = match entry {
+                        Entry::Dir => self.fs.mkdir(dir, name),
+                        Entry::File(size) => self.fs.touch(dir, name, size),
+                    };
+                    if result.is_err() {
+                        self.errors.push(ValidationError {
+                            line,
+                            issue: Issue::ListingMismatch(self.fs.path(dir)),
+                        });
+                    }
+                }
+                self.listings.insert(dir, listing);
+            }
+        }
+    }
+}
+
+/// Strict counterpart of `parse_output` + `FileSystem::from_ops`: builds the filesystem, or
+/// returns every inconsistency found in the transcript.
+pub fn validate_output(input: &str) -> Result<FileSystem, Vec<ValidationError>> {
+    let mut validator = Validator {
+        fs: FileSystem::new(),
+        cwd: ROOT,
+        listings: HashMap::new(),
+        current: None,
+        errors: vec![],
+    };
+
+    for (i, text) in input.lines().enumerate() {
+        match text.strip_prefix("$ ") {
+            _ if text.is_empty() => {}
+            Some(command) => validator.command(i + 1, command),
+            None => validator.output(i + 1, text),
+        }
+    }
+    validator.finish_listing();
+
+    if validator.errors.is_empty() {
+        Ok(validator.fs)
+    } else {
+        Err(validator.errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn issues(input: &str) -> Vec<(usize, Issue)> {
+        validate_output(input)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| (e.line, e.issue))
+            .collect()
+    }
+
+    #[test]
+    fn example_is_valid() {
+        let fs = validate_output(include_str!("../example")).unwrap();
+        assert_eq!(fs.du("/"), Some(48381165));
+    }
+
+    #[test]
+    fn input_is_valid() {
+        let input = std::fs::read_to_string("input").unwrap();
+        assert!(validate_output(&input).is_ok());
+    }
+
+    #[test_case("$ cd /\n$ ls\ndir a\n$ cd b", 4, Issue::UnlistedDirectory("/b".to_string()))]
+    #[test_case("$ cd /\n$ ls\n1 a\n$ cd a", 4, Issue::NotADirectory("/a".to_string()))]
+    #[test_case("$ cd /\n$ ls\n1 a\n$ cd a/b", 4, Issue::NotADirectory("/a".to_string()) ; "through a file")]
+    #[test_case("$ cd /\n$ ls\ndir a\n$ cd a\n$ cd /x", 5, Issue::UnlistedDirectory("/x".to_string()) ; "absolute")]
+    #[test_case("$ cd /\n$ ls\ndir a\n$ cd a/../b", 4, Issue::UnlistedDirectory("/b".to_string()) ; "dot dot")]
+    #[test_case("$ cd /\n$ ls\ndir a\n$ cd ./a/b/c", 4, Issue::UnlistedDirectory("/a/b".to_string()) ; "first missing")]
+    #[test_case("$ cd /\n$ cd ..", 2, Issue::AboveRoot)]
+    #[test_case("$ cd /\n$ ls\n12a b", 3, Issue::InvalidSize("12a b".to_string()))]
+    #[test_case("$ cd /\n$ ls\nfoo", 3, Issue::InvalidEntry("foo".to_string()))]
+    #[test_case("$ cd /\n$ ls\n7 ../escaped", 3, Issue::InvalidEntry("7 ../escaped".to_string()) ; "parent in name")]
+    #[test_case("$ cd /\n$ ls\n5 /abs/path", 3, Issue::InvalidEntry("5 /abs/path".to_string()) ; "absolute name")]
+    #[test_case("$ cd /\n$ ls\ndir .", 3, Issue::InvalidEntry("dir .".to_string()) ; "current dir name")]
+    #[test_case("$ cd /\n$ ls\n1 a\0b", 3, Issue::InvalidEntry("1 a\0b".to_string()) ; "nul in name")]
+    #[test_case("$ cd /\n$ rm -rf a", 2, Issue::UnknownCommand("rm -rf a".to_string()))]
+    #[test_case("$ cd", 1, Issue::UnknownCommand("cd".to_string()))]
+    #[test_case("1 a\n$ cd /", 1, Issue::UnexpectedOutput("1 a".to_string()))]
+    fn single_issue_is_reported(input: &str, line: usize, issue: Issue) {
+        assert_eq!(issues(input), vec![(line, issue)]);
+    }
+
+    #[test]
+    fn conflicting_sizes_are_reported() {
+        let input = "$ cd /\n$ ls\n1 a\n2 b\n$ ls\n1 a\n3 b";
+        assert_eq!(
+            issues(input),
+            vec![(
+                7,
+                Issue::SizeConflict {
+                    path: "/b".to_string(),
+                    first: 2,
+                    second: 3
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn conflicting_listings_are_reported() {
+        let input = "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n1 x\n$ cd /\n$ cd a\n$ ls\n2 x\n1 y";
+        assert_eq!(
+            issues(input),
+            vec![
+                (9, Issue::ListingMismatch("/a".to_string())),
+                (
+                    10,
+                    Issue::SizeConflict {
+                        path: "/a/x".to_string(),
+                        first: 1,
+                        second: 2
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_issue_is_reported() {
+        let input = "$ cd /\n$ cd ..\n$ ls\nx a\n$ cd nope\n$ pwd";
+        let errors = validate_output(input).unwrap_err();
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "line 2: cd .. above the root",
+                "line 4: invalid size in \"x a\"",
+                "line 5: cd into unlisted directory /nope",
+                "line 6: unknown command \"pwd\"",
+            ]
+        );
+    }
+}
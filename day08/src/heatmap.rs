@@ -0,0 +1,300 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use crate::{survey, Direction, Forest, TreeView};
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layer {
+    Heights,
+    Visibility,
+    ScenicScores,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+const BEST: Rgb = [255, 0, 0];
+const SIGHT_LINE: Rgb = [255, 0, 255];
+
+fn lerp(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    let mut c = [0; 3];
+    for i in 0..3 {
+        c[i] = (from[i] as f64 + (to[i] as f64 - from[i] as f64) * t.clamp(0.0, 1.0)).round() as u8;
+    }
+    c
+}
+
+/// Position of the tree with the highest scenic score, the first one on ties.
+pub fn best_tree(views: &[Vec<TreeView>]) -> Option<(usize, usize)> {
+    views
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, v)| (i, j, v)))
+        .fold(
+            None,
+            |best: Option<(usize, usize, u32)>, (i, j, v)| match best {
+                Some((_, _, score)) if score >= v.scenic_score() => best,
+                _ => Some((i, j, v.scenic_score())),
+            },
+        )
+        .map(|(i, j, _)| (i, j))
+}
+
+/// Trees seen from `(i, j)`, walking outwards in every direction up to the viewing distance.
+pub fn sight_lines(views: &[Vec<TreeView>], (i, j): (usize, usize)) -> Vec<(usize, usize)> {
+    let view = views[i][j];
+    Direction::ALL
+        .iter()
+        .flat_map(|&direction| {
+            (1..=view.viewing_distance[direction as usize] as usize).map(move |d| match direction {
+                Direction::Top => (i - d, j),
+                Direction::Left => (i, j - d),
+                Direction::Bottom => (i + d, j),
+                Direction::Right => (i, j + d),
+            })
+        })
+        .collect()
+}
+
+/// One pixel per tree, with the best tree and its sight lines highlighted.
+pub fn render(forest: &Forest, layer: Layer) -> Image {
+    let views = survey(forest);
+    let max_score = views
+        .iter()
+        .flatten()
+        .map(|v| v.scenic_score())
+        .max()
+        .unwrap_or(0);
+
+    let mut image = Image {
+        width: forest.width(),
+        height: forest.height(),
+        pixels: views
+            .iter()
+            .flatten()
+            .map(|view| match layer {
+                Layer::Heights => lerp([10, 40, 10], [120, 230, 90], view.height as f64 / 9.0),
+                Layer::Visibility if view.is_visible() => [250, 220, 60],
+                Layer::Visibility => [40, 40, 60],
+                Layer::ScenicScores => lerp(
+                    [20, 20, 80],
+                    [250, 120, 20],
+                    (view.scenic_score() as f64 / max_score.max(1) as f64).sqrt(),
+                ),
+            })
+            .collect(),
+    };
+
+    if let Some(best) = best_tree(&views) {
+        for (i, j) in sight_lines(&views, best) {
+            let pixel = &mut image.pixels[i * image.width + j];
+            *pixel = lerp(*pixel, SIGHT_LINE, 0.6);
+        }
+        image.pixels[best.0 * image.width + best.1] = BEST;
+    }
+    image
+}
+
+/// Coloured terminal view using 24-bit ANSI backgrounds, each cell shows the tree height.
+pub fn render_terminal(forest: &Forest, layer: Layer) -> String {
+    let image = render(forest, layer);
+    let mut out = String::new();
+    for (row, pixels) in forest.iter().zip(image.pixels.chunks(image.width)) {
+        for (height, [r, g, b]) in row.iter().zip(pixels) {
+            write!(out, "\x1b[48;2;{r};{g};{b}m{height} ").unwrap();
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+impl Image {
+    /// Every pixel becomes a `factor` x `factor` square.
+    pub fn scaled(&self, factor: usize) -> Image {
+        Image {
+            width: self.width * factor,
+            height: self.height * factor,
+            pixels: (0..self.height * factor)
+                .flat_map(|y| {
+                    (0..self.width * factor)
+                        .map(move |x| self.pixels[(y / factor) * self.width + x / factor])
+                })
+                .collect(),
+        }
+    }
+
+    /// Binary `P6` PPM.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(self.pixels.iter().flatten());
+        out
+    }
+
+    /// 8-bit RGB PNG. The image data is stored without compression, so no deflate
+    /// implementation is needed.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw.push(0); // no filter
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let blocks = raw.chunks(u16::MAX as usize).collect::<Vec<_>>();
+        for (i, block) in blocks.iter().enumerate() {
+            let len = block.len() as u16;
+            zlib.push(u8::from(i == blocks.len() - 1));
+            zlib.extend(len.to_le_bytes());
+            zlib.extend((!len).to_le_bytes());
+            zlib.extend(*block);
+        }
+        if blocks.is_empty() {
+            zlib.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut out, b"IHDR", &header);
+        png_chunk(&mut out, b"IDAT", &zlib);
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    /// Writes a PNG or, for any other extension, a PPM.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => fs::write(path, self.to_png()),
+            _ => fs::write(path, self.to_ppm()),
+        }
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    out.extend(crc32(&[&kind[..], data].concat()).to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const EXAMPLE: &str = "30373\n25512\n65332\n33549\n35390";
+
+    #[test]
+    fn best_tree_and_sight_lines_work() {
+        let views = survey(&parse_input(EXAMPLE).unwrap());
+        let best = best_tree(&views).unwrap();
+        assert_eq!(best, (3, 2));
+        assert_eq!(
+            sight_lines(&views, best),
+            vec![(2, 2), (1, 2), (3, 1), (3, 0), (4, 2), (3, 3), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn render_highlights_the_best_tree() {
+        let forest = parse_input(EXAMPLE).unwrap();
+        for layer in [Layer::Heights, Layer::Visibility, Layer::ScenicScores] {
+            let image = render(&forest, layer);
+            assert_eq!((image.width, image.height), (5, 5));
+            assert_eq!(image.pixels[3 * 5 + 2], BEST);
+        }
+
+        let image = render(&forest, Layer::Visibility);
+        // hidden, visible, and on a sight line of the best tree
+        assert_eq!(image.pixels[5 + 3], [40, 40, 60]);
+        assert_eq!(image.pixels[0], [250, 220, 60]);
+        assert_eq!(image.pixels[2 * 5 + 2], lerp([40, 40, 60], SIGHT_LINE, 0.6));
+    }
+
+    #[test]
+    fn terminal_works() {
+        let out = render_terminal(&parse_input("12\n34").unwrap(), Layer::Visibility);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[48;2;"));
+        assert!(lines[1].ends_with("4 \x1b[0m"));
+    }
+
+    #[test]
+    fn scaled_works() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![[1, 1, 1], [2, 2, 2]],
+        };
+        let scaled = image.scaled(2);
+        assert_eq!((scaled.width, scaled.height), (4, 2));
+        assert_eq!(
+            scaled.pixels,
+            [[1, 1, 1], [1, 1, 1], [2, 2, 2], [2, 2, 2]].repeat(2)
+        );
+    }
+
+    #[test]
+    fn ppm_works() {
+        let image = Image {
+            width: 1,
+            height: 2,
+            pixels: vec![[1, 2, 3], [4, 5, 6]],
+        };
+        assert_eq!(image.to_ppm(), b"P6\n1 2\n255\n\x01\x02\x03\x04\x05\x06");
+    }
+
+    #[test]
+    fn checksums_work() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_works() {
+        let image = Image {
+            width: 1,
+            height: 1,
+            pixels: vec![[255, 0, 0]],
+        };
+        let png = image.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..29], [0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(&png[37..41], b"IDAT");
+        // zlib header, one final stored block of 4 bytes: filter byte + pixel
+        assert_eq!(&png[41..52], [0x78, 1, 1, 4, 0, 0xfb, 0xff, 0, 255, 0, 0]);
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+}
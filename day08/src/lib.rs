@@ -1,121 +1,245 @@
-pub type Forest = Vec<Vec<i32>>;
+pub mod heatmap;
+pub mod sight;
 
-pub fn parse_input(input: &str) -> Result<Forest, String> {
-    input
+use std::{fmt, ops::Deref};
+
+/// Positions are 1-based, as in an editor.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ForestError {
+    Empty,
+    EmptyRow {
+        row: usize,
+    },
+    Ragged {
+        row: usize,
+        column: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidDigit {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for ForestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForestError::Empty => write!(f, "Forest is empty."),
+            ForestError::EmptyRow { row } => write!(f, "Row {row} is empty."),
+            ForestError::Ragged {
+                row,
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Row {row} has {found} trees instead of {expected}, column {column}."
+            ),
+            ForestError::InvalidDigit { row, column, found } => {
+                write!(
+                    f,
+                    "Failed to parse {found:?} as digit, row {row} column {column}."
+                )
+            }
+        }
+    }
+}
+
+/// A non-empty, rectangular grid of tree heights. Derefs to its rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Forest {
+    rows: Vec<Vec<i32>>,
+}
+
+impl Forest {
+    pub fn new(rows: Vec<Vec<i32>>) -> Result<Self, ForestError> {
+        let width = rows.first().ok_or(ForestError::Empty)?.len();
+        for (i, row) in rows.iter().enumerate() {
+            if row.is_empty() {
+                return Err(ForestError::EmptyRow { row: i + 1 });
+            }
+            if row.len() != width {
+                return Err(ForestError::Ragged {
+                    row: i + 1,
+                    column: row.len().min(width) + 1,
+                    expected: width,
+                    found: row.len(),
+                });
+            }
+        }
+        Ok(Forest { rows })
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+impl Deref for Forest {
+    type Target = [Vec<i32>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.rows
+    }
+}
+
+impl TryFrom<Vec<Vec<i32>>> for Forest {
+    type Error = ForestError;
+
+    fn try_from(rows: Vec<Vec<i32>>) -> Result<Self, Self::Error> {
+        Forest::new(rows)
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<Forest, ForestError> {
+    let rows = input
         .lines()
-        .map(|line| {
+        .enumerate()
+        .map(|(i, line)| {
             line.chars()
-                .map(|c| {
+                .enumerate()
+                .map(|(j, c)| {
                     c.to_digit(10)
-                        .ok_or_else(|| "Failed to parse char as digit.".to_string())
                         .map(|c| c as i32)
+                        .ok_or(ForestError::InvalidDigit {
+                            row: i + 1,
+                            column: j + 1,
+                            found: c,
+                        })
                 })
-                .collect::<Result<Vec<i32>, String>>()
+                .collect::<Result<Vec<i32>, ForestError>>()
         })
-        .collect()
+        .collect::<Result<Vec<_>, _>>()?;
+    Forest::new(rows)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Top,
+    Left,
+    Bottom,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Top,
+        Direction::Left,
+        Direction::Bottom,
+        Direction::Right,
+    ];
 }
 
-pub fn count_visible(forest: Forest) -> usize {
-    let m = forest.len();
-    let n = forest[0].len();
-    if m < 3 {
-        return m * n;
+/// What a single tree sees, indexed by `Direction as usize`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TreeView {
+    pub height: i32,
+    pub visible_from: [bool; 4],
+    pub viewing_distance: [u32; 4],
+}
+
+impl TreeView {
+    pub fn is_visible_from(&self, direction: Direction) -> bool {
+        self.visible_from[direction as usize]
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible_from.iter().any(|&v| v)
     }
-    if n < 3 {
-        return m * n;
+
+    pub fn scenic_score(&self) -> u32 {
+        self.viewing_distance.iter().product()
+    }
+}
+
+/// Walks a line of trees and, looking back towards the start of the line, tells for every tree
+/// whether it sees the edge and how many trees it sees. The stack only keeps trees that are not
+/// hidden behind a later, at least as tall one, so every tree is pushed and popped once.
+fn sweep(heights: impl Iterator<Item = i32>) -> Vec<(bool, u32)> {
+    let mut stack: Vec<(usize, i32)> = vec![];
+    heights
+        .enumerate()
+        .map(|(k, height)| {
+            while stack.last().is_some_and(|&(_, h)| h < height) {
+                stack.pop();
+            }
+            let seen = match stack.last() {
+                Some(&(blocker, _)) => (false, (k - blocker) as u32),
+                None => (true, k as u32),
+            };
+            stack.push((k, height));
+            seen
+        })
+        .collect()
+}
+
+/// Visibility and viewing distances of every tree, in O(rows * cols).
+pub fn survey(forest: &Forest) -> Vec<Vec<TreeView>> {
+    let (m, n) = (forest.height(), forest.width());
+    let mut views = forest
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&height| TreeView {
+                    height,
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut record =
+        |i: usize, j: usize, direction: Direction, (visible, distance): (bool, u32)| {
+            views[i][j].visible_from[direction as usize] = visible;
+            views[i][j].viewing_distance[direction as usize] = distance;
+        };
+
+    for (i, row) in forest.iter().enumerate() {
+        for (j, seen) in sweep(row.iter().copied()).into_iter().enumerate() {
+            record(i, j, Direction::Left, seen);
+        }
+        for (j, seen) in sweep(row.iter().rev().copied()).into_iter().enumerate() {
+            record(i, n - 1 - j, Direction::Right, seen);
+        }
     }
-    // for row in forest.iter() {
-    //     println!("{:?}", row);
-    // }
-    // println!();
-    forest.iter().enumerate().fold(0, |acc, (i, row)| {
-        // eprintln!("{:?}", row);
-        acc + row
-            .iter()
+    for j in 0..n {
+        for (i, seen) in sweep(forest.iter().map(|row| row[j]))
+            .into_iter()
+            .enumerate()
+        {
+            record(i, j, Direction::Top, seen);
+        }
+        for (i, seen) in sweep(forest.iter().rev().map(|row| row[j]))
+            .into_iter()
             .enumerate()
-            .filter(|(j, &c)| {
-                let j = *j;
-                // eprintln!("{i}-{j}: {c}");
-
-                if i == 0 || i == m - 1 {
-                    // eprintln!("    visible, top or bottom edge");
-                    return true;
-                }
-
-                if j == 0 || j == n - 1 {
-                    // eprintln!("    visible, left or right edge");
-                    return true;
-                }
-                if row[0..j].iter().all(|&x| x < c) {
-                    // eprintln!("   visible from left");
-                    return true;
-                }
-                if row[j + 1..n].iter().all(|&x| x < c) {
-                    // eprintln!("   visible from right");
-                    return true;
-                }
-
-                if forest[0..i].iter().all(|row| row[j] < c) {
-                    // eprintln!("   visible from top");
-                    return true;
-                }
-                if forest[i + 1..m].iter().all(|row| row[j] < c) {
-                    // eprintln!("   visible from bottom");
-                    return true;
-                }
-
-                // eprintln!("   hidden");
-                false
-            })
-            .count()
-    })
+        {
+            record(m - 1 - i, j, Direction::Bottom, seen);
+        }
+    }
+
+    views
+}
+
+pub fn count_visible(forest: &Forest) -> usize {
+    survey(forest)
+        .iter()
+        .flatten()
+        .filter(|view| view.is_visible())
+        .count()
 }
 
 pub fn calculate_scenic_scores(forest: &Forest) -> Vec<Vec<(i32, u32)>> {
-    forest
+    survey(forest)
         .iter()
-        .enumerate()
-        .map(|(i, row)| {
+        .map(|row| {
             row.iter()
-                .enumerate()
-                .map(|(j, &c)| {
-                    let mut scores: [u32; 4] = [0, 0, 0, 0];
-
-                    // top
-                    for row in forest[0..i].iter().rev() {
-                        scores[0] += 1;
-                        if row[j] >= c {
-                            break;
-                        }
-                    }
-
-                    // left
-                    for x in row[0..j].iter().rev() {
-                        scores[1] += 1;
-                        if *x >= c {
-                            break;
-                        }
-                    }
-
-                    // bottom
-                    for row in forest[i + 1..].iter() {
-                        scores[2] += 1;
-                        if row[j] >= c {
-                            break;
-                        }
-                    }
-
-                    // right
-                    for x in row[j + 1..].iter() {
-                        scores[3] += 1;
-                        if *x >= c {
-                            break;
-                        }
-                    }
-
-                    let score = scores.iter().product();
-                    (c, score)
-                })
+                .map(|view| (view.height, view.scenic_score()))
                 .collect()
         })
         .collect()
@@ -130,8 +254,52 @@ mod tests {
     #[test_case(vec![vec![1,2,3], vec![1,2,3]], 6)]
     #[test_case(vec![vec![1], vec![2]], 2)]
     #[test_case(vec![vec![1], vec![2], vec![3]], 3)]
-    fn test_count_visible_on_small_forest(forest: Forest, visible: usize) {
-        assert_eq!(count_visible(forest), visible);
+    fn test_count_visible_on_small_forest(forest: Vec<Vec<i32>>, visible: usize) {
+        let forest = Forest::new(forest).unwrap();
+        assert_eq!(count_visible(&forest), visible);
+    }
+
+    #[test_case("", ForestError::Empty)]
+    #[test_case("123\n\n123", ForestError::EmptyRow { row: 2 })]
+    #[test_case("123\n12\n123", ForestError::Ragged { row: 2, column: 3, expected: 3, found: 2 })]
+    #[test_case("123\n123\n1234", ForestError::Ragged { row: 3, column: 4, expected: 3, found: 4 })]
+    #[test_case("123\n1a3", ForestError::InvalidDigit { row: 2, column: 2, found: 'a' })]
+    fn invalid_forests_are_rejected(input: &str, error: ForestError) {
+        assert_eq!(parse_input(input), Err(error));
+    }
+
+    #[test]
+    fn forest_errors_display_position() {
+        assert_eq!(
+            parse_input("12\n1").unwrap_err().to_string(),
+            "Row 2 has 1 trees instead of 2, column 2."
+        );
+        assert_eq!(
+            Forest::try_from(vec![vec![1], vec![]]),
+            Err(ForestError::EmptyRow { row: 2 })
+        );
+    }
+
+    #[test_case(1, 1, [true, true, false, false])]
+    #[test_case(1, 2, [true, false, false, true])]
+    #[test_case(1, 3, [false, false, false, false])]
+    #[test_case(2, 1, [false, false, false, true])]
+    #[test_case(2, 2, [false, false, false, false])]
+    #[test_case(3, 2, [false, true, true, false])]
+    #[test_case(0, 4, [true, false, false, true])]
+    fn visible_from_works(i: usize, j: usize, expected: [bool; 4]) {
+        let forest = parse_input("30373\n25512\n65332\n33549\n35390").unwrap();
+        assert_eq!(survey(&forest)[i][j].visible_from, expected);
+    }
+
+    #[test_case(1, 2, [1, 1, 2, 2], 4)]
+    #[test_case(3, 2, [2, 2, 1, 2], 8)]
+    #[test_case(0, 0, [0, 0, 2, 2], 0)]
+    fn viewing_distance_works(i: usize, j: usize, distances: [u32; 4], score: u32) {
+        let forest = parse_input("30373\n25512\n65332\n33549\n35390").unwrap();
+        let view = survey(&forest)[i][j];
+        assert_eq!(view.viewing_distance, distances);
+        assert_eq!(view.scenic_score(), score);
     }
 
     mod part1 {
@@ -147,7 +315,7 @@ mod tests {
 35390"#;
 
             let forest = parse_input(input).unwrap();
-            assert_eq!(count_visible(forest), 21);
+            assert_eq!(count_visible(&forest), 21);
         }
 
         #[test]
@@ -155,7 +323,7 @@ mod tests {
             let input = read_to_string("input").unwrap();
 
             let forest = parse_input(&input).unwrap();
-            assert_eq!(count_visible(forest), 1825);
+            assert_eq!(count_visible(&forest), 1825);
         }
     }
 
@@ -0,0 +1,336 @@
+use crate::Forest;
+
+/// Which way trees look and how many blockers they can see past.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SightRules {
+    /// `(row, col)` steps, one per direction.
+    pub directions: Vec<(isize, isize)>,
+    /// A view ends at the `blockers`-th tree at least as tall as the viewer, and the viewer is
+    /// visible from the edge only when there are fewer of them. `1` is the puzzle rule, and `0`
+    /// is treated as `1`.
+    pub blockers: usize,
+}
+
+impl SightRules {
+    /// Top, left, bottom and right, in the same order as `Direction`.
+    pub fn orthogonal() -> Self {
+        SightRules {
+            directions: vec![(-1, 0), (0, -1), (1, 0), (0, 1)],
+            blockers: 1,
+        }
+    }
+
+    /// The orthogonal directions followed by the four diagonals.
+    pub fn all_directions() -> Self {
+        let mut rules = Self::orthogonal();
+        rules
+            .directions
+            .extend([(-1, -1), (1, -1), (1, 1), (-1, 1)]);
+        rules
+    }
+}
+
+/// Like `TreeView`, with one entry per direction of the `SightRules` used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SightView {
+    pub height: i32,
+    pub visible_from: Vec<bool>,
+    pub viewing_distance: Vec<u32>,
+}
+
+impl SightView {
+    pub fn is_visible(&self) -> bool {
+        self.visible_from.iter().any(|&v| v)
+    }
+
+    pub fn scenic_score(&self) -> u32 {
+        self.viewing_distance.iter().product()
+    }
+}
+
+pub fn survey_with(forest: &Forest, rules: &SightRules) -> Vec<Vec<SightView>> {
+    let (m, n) = (forest.height() as isize, forest.width() as isize);
+    let limit = rules.blockers.max(1);
+
+    forest
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, &height)| {
+                    let (visible_from, viewing_distance) = rules
+                        .directions
+                        .iter()
+                        .map(|&(di, dj)| {
+                            let (mut i, mut j) = (i as isize + di, j as isize + dj);
+                            let (mut distance, mut blockers) = (0, 0);
+                            while (0..m).contains(&i) && (0..n).contains(&j) {
+                                distance += 1;
+                                if forest[i as usize][j as usize] >= height {
+                                    blockers += 1;
+                                    if blockers == limit {
+                                        break;
+                                    }
+                                }
+                                (i, j) = (i + di, j + dj);
+                            }
+                            (blockers < limit, distance)
+                        })
+                        .unzip();
+                    SightView {
+                        height,
+                        visible_from,
+                        viewing_distance,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// An eye at a point of the forest floor plan, where tree `(i, j)` covers the square
+/// `[i, i + 1) x [j, j + 1)` and rises `forest[i][j]` high.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observer {
+    pub row: f64,
+    pub col: f64,
+    pub height: f64,
+}
+
+impl Observer {
+    /// Standing at the centre of a tree, `height` above the ground.
+    pub fn at(i: usize, j: usize, height: f64) -> Self {
+        Observer {
+            row: i as f64 + 0.5,
+            col: j as f64 + 0.5,
+            height,
+        }
+    }
+
+    fn cell(&self) -> (isize, isize) {
+        (self.row.floor() as isize, self.col.floor() as isize)
+    }
+}
+
+/// Cells crossed by the segment `from -> to`, with the part of the segment (`0..=1`) inside each.
+fn traverse(from: (f64, f64), to: (f64, f64)) -> Vec<((isize, isize), f64, f64)> {
+    let axis = |from: f64, to: f64| {
+        let d = to - from;
+        let cell = from.floor();
+        if d > 0.0 {
+            (1, (cell + 1.0 - from) / d, 1.0 / d)
+        } else if d < 0.0 {
+            (-1, (from - cell) / -d, 1.0 / -d)
+        } else {
+            (0, f64::INFINITY, f64::INFINITY)
+        }
+    };
+    let (step_r, mut next_r, delta_r) = axis(from.0, to.0);
+    let (step_c, mut next_c, delta_c) = axis(from.1, to.1);
+
+    let mut cell = (from.0.floor() as isize, from.1.floor() as isize);
+    let mut t = 0.0;
+    let mut cells = vec![];
+    while t < 1.0 {
+        let t_next = next_r.min(next_c).min(1.0);
+        // rounding can make a ray through a corner clip a neighbour for a tiny distance
+        if t_next - t > 1e-9 {
+            cells.push((cell, t, t_next));
+        }
+        // stepping both at once when crossing a corner exactly
+        if next_r <= t_next {
+            cell.0 += step_r;
+            next_r += delta_r;
+        }
+        if next_c <= t_next {
+            cell.1 += step_c;
+            next_c += delta_c;
+        }
+        t = t_next;
+    }
+    cells
+}
+
+/// Whether the top of every tree can be seen from the observer. A tree is hidden when the line
+/// of sight runs at or below the top of a tree standing between them.
+pub fn viewshed(forest: &Forest, observer: &Observer) -> Vec<Vec<bool>> {
+    let height_at = |(i, j): (isize, isize)| {
+        forest
+            .get(usize::try_from(i).ok()?)?
+            .get(usize::try_from(j).ok()?)
+            .map(|&h| h as f64)
+    };
+
+    forest
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            (0..row.len())
+                .map(|j| {
+                    let target = (i as isize, j as isize);
+                    let top = row[j] as f64;
+                    let line = |t: f64| observer.height + (top - observer.height) * t;
+                    traverse(
+                        (observer.row, observer.col),
+                        (i as f64 + 0.5, j as f64 + 0.5),
+                    )
+                    .into_iter()
+                    .filter(|&(cell, _, _)| cell != target && cell != observer.cell())
+                    .all(|(cell, t_in, t_out)| match height_at(cell) {
+                        Some(h) => h < line(t_in).min(line(t_out)),
+                        None => true,
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Trees seen along a horizontal ray, `angle` in radians counter-clockwise from the direction
+/// of increasing columns. A tree is seen when its top, taken where the ray enters its cell,
+/// rises above everything seen before it.
+pub fn cast_ray(forest: &Forest, observer: &Observer, angle: f64) -> Vec<(usize, usize)> {
+    let (m, n) = (forest.height(), forest.width());
+    let length = (m + n) as f64 + 2.0;
+    let to = (
+        observer.row - angle.sin() * length,
+        observer.col + angle.cos() * length,
+    );
+
+    let mut horizon = f64::NEG_INFINITY;
+    let mut seen = vec![];
+    for ((i, j), t_in, _) in traverse((observer.row, observer.col), to) {
+        if (i, j) == observer.cell() {
+            continue;
+        }
+        let (Ok(i), Ok(j)) = (usize::try_from(i), usize::try_from(j)) else {
+            break;
+        };
+        if i >= m || j >= n {
+            break;
+        }
+        let slope = (forest[i][j] as f64 - observer.height) / (t_in * length);
+        if slope > horizon {
+            seen.push((i, j));
+            horizon = slope;
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, survey};
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "30373\n25512\n65332\n33549\n35390";
+
+    #[test]
+    fn orthogonal_rules_match_survey() {
+        let forest = parse_input(&std::fs::read_to_string("input").unwrap()).unwrap();
+        let views = survey_with(&forest, &SightRules::orthogonal());
+        for (row, expected) in views.iter().zip(survey(&forest)) {
+            for (view, expected) in row.iter().zip(expected) {
+                assert_eq!(view.visible_from, expected.visible_from);
+                assert_eq!(view.viewing_distance, expected.viewing_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_blockers_act_as_one() {
+        let forest = parse_input(EXAMPLE).unwrap();
+        let rules = SightRules {
+            blockers: 0,
+            ..SightRules::orthogonal()
+        };
+        assert_eq!(
+            survey_with(&forest, &rules),
+            survey_with(&forest, &SightRules::orthogonal())
+        );
+    }
+
+    #[test]
+    fn diagonals_work() {
+        let forest = parse_input(EXAMPLE).unwrap();
+        let views = survey_with(&forest, &SightRules::all_directions());
+        // the 5 at (3, 2) sees the edge over the 3 at (2, 3) and the 2 at (1, 4)
+        assert!(views[3][2].visible_from[7]);
+        assert_eq!(views[3][2].viewing_distance, vec![2, 2, 1, 2, 1, 1, 1, 2]);
+        assert_eq!(views[3][2].scenic_score(), 16);
+        // the hidden 1 at (1, 3) stays hidden
+        assert!(!views[1][3].is_visible());
+    }
+
+    #[test_case(1, false, 1)]
+    #[test_case(2, true, 2)]
+    fn blockers_work(blockers: usize, visible: bool, distance: u32) {
+        let forest = parse_input(EXAMPLE).unwrap();
+        let rules = SightRules {
+            blockers,
+            ..SightRules::orthogonal()
+        };
+        // the 3 at (2, 2) looking right, over another 3 and a 2
+        let view = &survey_with(&forest, &rules)[2][2];
+        assert_eq!(view.visible_from[3], visible);
+        assert_eq!(view.viewing_distance[3], distance);
+    }
+
+    #[test]
+    fn traverse_works() {
+        assert_eq!(
+            traverse((0.5, 0.5), (2.5, 1.5)),
+            vec![
+                ((0, 0), 0.0, 0.25),
+                ((1, 0), 0.25, 0.5),
+                ((1, 1), 0.5, 0.75),
+                ((2, 1), 0.75, 1.0)
+            ]
+        );
+        // through a corner
+        assert_eq!(
+            traverse((0.5, 0.5), (1.5, 1.5)),
+            vec![((0, 0), 0.0, 0.5), ((1, 1), 0.5, 1.0)]
+        );
+    }
+
+    #[test]
+    fn viewshed_works() {
+        let forest = parse_input("555\n595\n555").unwrap();
+        assert_eq!(
+            viewshed(&forest, &Observer::at(0, 0, 5.5)),
+            vec![
+                vec![true, true, true],
+                vec![true, true, false],
+                vec![true, false, false]
+            ]
+        );
+        assert!(viewshed(&forest, &Observer::at(0, 0, 40.0))
+            .iter()
+            .flatten()
+            .all(|&v| v));
+    }
+
+    #[test_case(0.0, vec![(0, 1)])]
+    #[test_case(10.0, vec![(0, 1), (0, 2), (0, 3), (0, 4)])]
+    fn cast_ray_works(height: f64, expected: Vec<(usize, usize)>) {
+        let forest = parse_input("01215").unwrap();
+        assert_eq!(
+            cast_ray(&forest, &Observer::at(0, 0, height), 0.0),
+            expected
+        );
+    }
+
+    #[test]
+    fn cast_ray_at_an_angle() {
+        let forest = parse_input("000\n010\n003").unwrap();
+        let seen = cast_ray(
+            &forest,
+            &Observer::at(0, 0, 0.5),
+            -std::f64::consts::FRAC_PI_4,
+        );
+        assert_eq!(seen, vec![(1, 1), (2, 2)]);
+    }
+}
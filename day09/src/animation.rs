@@ -0,0 +1,365 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use crate::{generate_motions, Vec2D};
+
+/// Inclusive box around every position ever taken by any knot, so all frames share a size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min_x: i32,
+    pub max_x: i32,
+    pub min_y: i32,
+    pub max_y: i32,
+}
+
+impl Bounds {
+    pub fn of(history: &[Vec<Vec2D>]) -> Self {
+        history.iter().flatten().fold(
+            Bounds {
+                min_x: 0,
+                max_x: 0,
+                min_y: 0,
+                max_y: 0,
+            },
+            |b, &(x, y)| Bounds {
+                min_x: b.min_x.min(x),
+                max_x: b.max_x.max(x),
+                min_y: b.min_y.min(y),
+                max_y: b.max_y.max(y),
+            },
+        )
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    Empty,
+    Visited,
+    Start,
+    Knot(usize),
+}
+
+impl Cell {
+    /// The puzzle's notation: `H` for the head, `T` for the tail of a two knot rope, otherwise
+    /// the knot index.
+    pub fn to_char(self, length: usize) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Visited => '#',
+            Cell::Start => 's',
+            Cell::Knot(0) => 'H',
+            Cell::Knot(1) if length == 2 => 'T',
+            Cell::Knot(k) => char::from_digit((k % 10) as u32, 10).unwrap(),
+        }
+    }
+
+    fn palette_index(self) -> u8 {
+        match self {
+            Cell::Empty => 0,
+            Cell::Visited => 1,
+            Cell::Start => 2,
+            Cell::Knot(0) => 4,
+            Cell::Knot(_) => 3,
+        }
+    }
+}
+
+const PALETTE: [[u8; 3]; 8] = [
+    [20, 20, 30],
+    [70, 110, 160],
+    [240, 200, 60],
+    [90, 200, 90],
+    [230, 60, 50],
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+];
+
+/// Every unit step of a rope, drawn on a fixed canvas.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub history: Vec<Vec<Vec2D>>,
+    pub bounds: Bounds,
+    /// Draw the cells visited by the tail so far.
+    pub overlay: bool,
+}
+
+impl Animation {
+    pub fn new(data: &[Vec2D], length: usize) -> Self {
+        Self::from_history(generate_motions(data, length))
+    }
+
+    pub fn from_history(history: Vec<Vec<Vec2D>>) -> Self {
+        Animation {
+            bounds: Bounds::of(&history),
+            history,
+            overlay: true,
+        }
+    }
+
+    fn length(&self) -> usize {
+        self.history.first().map_or(0, |frame| frame.len())
+    }
+
+    /// Calls `f` with the cells of every frame, rows top to bottom.
+    pub fn for_each_frame(&self, mut f: impl FnMut(usize, &[Vec<Cell>])) {
+        let b = self.bounds;
+        let mut visited: HashSet<Vec2D> = HashSet::new();
+        for (step, frame) in self.history.iter().enumerate() {
+            if let Some(&tail) = frame.last() {
+                visited.insert(tail);
+            }
+
+            let mut cells = vec![vec![Cell::Empty; b.width()]; b.height()];
+            let mut draw = |(x, y): Vec2D, cell| {
+                cells[(y - b.min_y) as usize][(x - b.min_x) as usize] = cell;
+            };
+            if self.overlay {
+                visited.iter().for_each(|&p| draw(p, Cell::Visited));
+            }
+            draw((0, 0), Cell::Start);
+            // lower knots are drawn last so they end up on top
+            for (k, &p) in frame.iter().enumerate().rev() {
+                draw(p, Cell::Knot(k));
+            }
+            f(step, &cells);
+        }
+    }
+
+    pub fn text_frames(&self) -> Vec<String> {
+        let length = self.length();
+        let mut frames = vec![];
+        self.for_each_frame(|_, cells| {
+            frames.push(
+                cells
+                    .iter()
+                    .map(|row| row.iter().map(|c| c.to_char(length)).collect::<String>() + "\n")
+                    .collect(),
+            )
+        });
+        frames
+    }
+
+    /// Draws every frame in place in the terminal.
+    pub fn play(&self, out: &mut impl Write, delay: Duration) -> io::Result<()> {
+        for frame in self.text_frames() {
+            write!(out, "\x1b[2J\x1b[H{frame}")?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// `frame_00000.txt`, `frame_00001.txt`, ...
+    pub fn write_text_frames(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (step, frame) in self.text_frames().iter().enumerate() {
+            fs::write(dir.join(format!("frame_{step:05}.txt")), frame)?;
+        }
+        Ok(())
+    }
+
+    /// `frame_00000.ppm`, ..., every cell `scale` pixels wide.
+    pub fn write_ppm_frames(&self, dir: &Path, scale: usize) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let (width, height) = (self.bounds.width() * scale, self.bounds.height() * scale);
+        let mut result = Ok(());
+        self.for_each_frame(|step, cells| {
+            if result.is_err() {
+                return;
+            }
+            let mut ppm = format!("P6\n{width} {height}\n255\n").into_bytes();
+            ppm.extend(
+                scaled(cells, scale).flat_map(|cell| PALETTE[cell.palette_index() as usize]),
+            );
+            result = fs::write(dir.join(format!("frame_{step:05}.ppm")), ppm);
+        });
+        result
+    }
+
+    /// Looping animated GIF, `delay` in hundredths of a second between frames. Fails when a frame
+    /// would be more than 65535 pixels wide or high, the most a GIF can hold.
+    pub fn to_gif(&self, scale: usize, delay: u16) -> Result<Vec<u8>, String> {
+        let dimension = |cells: usize| {
+            cells
+                .checked_mul(scale)
+                .and_then(|pixels| u16::try_from(pixels).ok())
+                .ok_or_else(|| format!("{cells} cells at scale {scale} exceed the GIF size limit"))
+        };
+        let (width, height) = (
+            dimension(self.bounds.width())?,
+            dimension(self.bounds.height())?,
+        );
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        // global colour table of 8 entries, 8 bits per channel
+        gif.extend([0xf2, 0, 0]);
+        gif.extend(PALETTE.iter().flatten());
+        gif.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        self.for_each_frame(|_, cells| {
+            gif.extend([0x21, 0xf9, 0x04, 0x00]);
+            gif.extend(delay.to_le_bytes());
+            gif.extend([0x00, 0x00]);
+
+            gif.push(0x2c);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend(width.to_le_bytes());
+            gif.extend(height.to_le_bytes());
+            gif.push(0);
+
+            gif.push(3); // minimum code size
+            let data = lzw_uncompressed(scaled(cells, scale).map(Cell::palette_index));
+            for block in data.chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0);
+        });
+        gif.push(0x3b);
+        Ok(gif)
+    }
+}
+
+fn scaled(cells: &[Vec<Cell>], scale: usize) -> impl Iterator<Item = Cell> + '_ {
+    cells.iter().flat_map(move |row| {
+        (0..scale).flat_map(move |_| row.iter().flat_map(move |&c| (0..scale).map(move |_| c)))
+    })
+}
+
+/// GIF image data with every pixel sent as a literal 4 bit code. A clear code is sent before
+/// the decoder's table would grow past 4 bits, which avoids implementing real LZW compression.
+fn lzw_uncompressed(pixels: impl Iterator<Item = u8>) -> Vec<u8> {
+    const CLEAR: u16 = 8;
+    const END: u16 = 9;
+
+    let mut out = vec![];
+    let (mut buffer, mut bits) = (0u32, 0);
+    let mut emit = |code: u16| {
+        buffer |= (code as u32) << bits;
+        bits += 4;
+        while bits >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+
+    for (i, pixel) in pixels.enumerate() {
+        if i % 6 == 0 {
+            emit(CLEAR);
+        }
+        emit(pixel as u16);
+    }
+    emit(END);
+    if bits > 0 {
+        out.push(buffer as u8);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+
+    #[test]
+    fn text_frames_match_example_history() {
+        let mut animation = Animation::new(&parse_input(EXAMPLE).unwrap(), 2);
+        animation.overlay = false;
+        assert_eq!(
+            animation.text_frames().join("\n"),
+            include_str!("../example_history")
+        );
+    }
+
+    #[test]
+    fn bounds_cover_the_whole_history() {
+        let animation = Animation::new(&parse_input(EXAMPLE).unwrap(), 2);
+        assert_eq!(
+            animation.bounds,
+            Bounds {
+                min_x: 0,
+                max_x: 5,
+                min_y: -4,
+                max_y: 0
+            }
+        );
+    }
+
+    #[test]
+    fn overlay_works() {
+        let animation = Animation::new(&parse_input(EXAMPLE).unwrap(), 2);
+        assert_eq!(
+            animation.text_frames().last().unwrap(),
+            "..##..\n...##.\n.TH##.\n....#.\ns###..\n"
+        );
+    }
+
+    #[test]
+    fn long_ropes_use_knot_numbers() {
+        let animation = Animation::new(&parse_input("R 3").unwrap(), 10);
+        assert_eq!(animation.text_frames().last().unwrap(), "321H\n");
+    }
+
+    #[test]
+    fn frames_are_written() {
+        let dir = std::env::temp_dir().join(format!("day09-frames-{}", std::process::id()));
+        let animation = Animation::new(&parse_input("R 2").unwrap(), 2);
+        animation.write_text_frames(&dir).unwrap();
+        animation.write_ppm_frames(&dir, 2).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("frame_00002.txt")).unwrap(),
+            "sTH\n"
+        );
+        let ppm = fs::read(dir.join("frame_00000.ppm")).unwrap();
+        assert!(ppm.starts_with(b"P6\n6 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 6 * 2 * 3);
+        assert!(!dir.join("frame_00003.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lzw_uncompressed_works() {
+        // clear, 1, 2, end packed low nibble first
+        assert_eq!(lzw_uncompressed([1, 2].into_iter()), vec![0x18, 0x92]);
+        // a clear code every 6 pixels
+        assert_eq!(
+            lzw_uncompressed([0; 7].into_iter()),
+            vec![0x08, 0x00, 0x00, 0x80, 0x90]
+        );
+    }
+
+    #[test]
+    fn gif_works() {
+        let animation = Animation::new(&parse_input("R 2").unwrap(), 2);
+        let gif = animation.to_gif(1, 10).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x03\x00\x01\x00\xf2"));
+        assert_eq!(gif.iter().filter(|&&b| b == 0x2c).count(), 3);
+        assert_eq!(gif.last(), Some(&0x3b));
+
+        // 3 cells wide, so 65538 pixels
+        assert_eq!(
+            animation.to_gif(21846, 10),
+            Err("3 cells at scale 21846 exceed the GIF size limit".to_string())
+        );
+    }
+}
@@ -1,13 +1,18 @@
-type Vec2D = (i32, i32);
+use std::collections::HashSet;
+
+pub mod animation;
+pub mod physics;
+pub mod point;
+pub mod stats;
+
+use physics::Physics;
+pub use point::{Point, Vec2D, Vec3D};
+use stats::KnotStats;
 
 fn parse_input_line(line: &str) -> Result<Vec2D, String> {
-    let (direction, magnitude) = match line
+    let (direction, magnitude) = line
         .split_once(' ')
-        .ok_or_else(|| "Failed to split line in 2".to_string())
-    {
-        Ok(pair) => pair,
-        Err(e) => return Err(e),
-    };
+        .ok_or_else(|| "Failed to split line in 2".to_string())?;
 
     let magnitude: i32 = match magnitude.parse::<u32>() {
         Ok(d) => d as i32,
@@ -19,6 +24,10 @@ fn parse_input_line(line: &str) -> Result<Vec2D, String> {
         "L" => Ok((-magnitude, 0)),
         "D" => Ok((0, magnitude)),
         "R" => Ok((magnitude, 0)),
+        "UL" => Ok((-magnitude, -magnitude)),
+        "UR" => Ok((magnitude, -magnitude)),
+        "DL" => Ok((-magnitude, magnitude)),
+        "DR" => Ok((magnitude, magnitude)),
         _ => Err("Unknown str, cannot parse.".to_string()),
     }
 }
@@ -27,6 +36,29 @@ pub fn parse_input(input: &str) -> Result<Vec<Vec2D>, String> {
     input.lines().map(parse_input_line).collect()
 }
 
+/// Like `parse_input_line`, with `F` and `B` moving forwards and backwards along the third axis.
+fn parse_input_line_3d(line: &str) -> Result<Vec3D, String> {
+    match line.split_once(' ') {
+        Some((direction @ ("F" | "B"), magnitude)) => {
+            let magnitude = magnitude.parse::<u32>().map_err(|e| e.to_string())? as i32;
+            Ok((
+                0,
+                0,
+                if direction == "F" {
+                    magnitude
+                } else {
+                    -magnitude
+                },
+            ))
+        }
+        _ => parse_input_line(line).map(|(x, y)| (x, y, 0)),
+    }
+}
+
+pub fn parse_input_3d(input: &str) -> Result<Vec<Vec3D>, String> {
+    input.lines().map(parse_input_line_3d).collect()
+}
+
 fn apply_motion(prev: &[Vec2D], (dx, dy): &Vec2D) -> Vec<Vec2D> {
     prev.windows(2).fold(vec![], |mut acc, pair| {
         let tail = pair[1];
@@ -47,14 +79,113 @@ fn apply_motion(prev: &[Vec2D], (dx, dy): &Vec2D) -> Vec<Vec2D> {
     })
 }
 
+/// Splits a motion such as `R 4` into the unit steps the head actually takes.
+pub fn unit_steps<P: Point>(motion: P) -> impl Iterator<Item = P> {
+    (0..motion.chebyshev()).map(move |_| motion.map(i32::signum))
+}
+
+/// Every position of every knot after each unit step, starting with all knots at the origin.
 pub fn generate_motions(data: &[Vec2D], length: usize) -> Vec<Vec<Vec2D>> {
-    data.iter()
-        .fold(vec![vec![data[0]; length]], |mut acc, motion| {
+    data.iter().flat_map(|&motion| unit_steps(motion)).fold(
+        vec![vec![(0, 0); length]],
+        |mut acc, step| {
             let prev = acc.last().unwrap();
-            acc.push(apply_motion(prev, motion));
+            acc.push(apply_motion(prev, &step));
 
             acc
-        })
+        },
+    )
+}
+
+/// Rope simulation that only keeps the current knot positions and statistics on the cells each
+/// knot visited, in the plane or, with `Vec3D`, in space.
+#[derive(Clone, Debug)]
+pub struct Rope<P = Vec2D> {
+    knots: Vec<P>,
+    stats: Vec<KnotStats<P>>,
+    steps: usize,
+    physics: Physics<P>,
+}
+
+impl<P: Point> Rope<P> {
+    /// Panics when `length` is 0, as every rope needs a head.
+    pub fn new(length: usize) -> Self {
+        Self::with_physics(length, Physics::default())
+    }
+
+    /// Panics when `length` is 0, as every rope needs a head.
+    pub fn with_physics(length: usize, physics: Physics<P>) -> Self {
+        assert!(length > 0, "A rope needs at least one knot");
+        Rope {
+            knots: vec![P::origin(); length],
+            stats: vec![KnotStats::default(); length],
+            steps: 0,
+            physics,
+        }
+    }
+
+    pub fn knots(&self) -> &[P] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> P {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> P {
+        *self.knots.last().unwrap()
+    }
+
+    /// Number of unit steps taken by the head.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn stats(&self, knot: usize) -> &KnotStats<P> {
+        &self.stats[knot]
+    }
+
+    pub fn visited(&self, knot: usize) -> HashSet<P> {
+        self.stats[knot].visits.keys().copied().collect()
+    }
+
+    pub fn tail_visited(&self) -> HashSet<P> {
+        self.visited(self.knots.len() - 1)
+    }
+
+    /// Moves the head by one unit step and lets the other knots follow.
+    pub fn step(&mut self, step: P) {
+        self.steps += 1;
+        self.knots[0] = self.knots[0].add(step);
+        self.stats[0].record(self.knots[0], self.steps);
+        for k in 1..self.knots.len() {
+            let next = self
+                .physics
+                .rule(k)
+                .follow(self.knots[k - 1], self.knots[k]);
+            if next == self.knots[k] {
+                break;
+            }
+            self.knots[k] = next;
+            self.stats[k].record(next, self.steps);
+        }
+    }
+
+    pub fn apply(&mut self, motion: P) {
+        unit_steps(motion).for_each(|step| self.step(step));
+    }
+}
+
+/// Panics when `length` is 0, as every rope needs a head.
+pub fn simulate<P: Point>(data: &[P], length: usize) -> Rope<P> {
+    simulate_with(data, length, Physics::default())
+}
+
+/// Panics when `length` is 0, as every rope needs a head.
+pub fn simulate_with<P: Point>(data: &[P], length: usize, physics: Physics<P>) -> Rope<P> {
+    let mut rope = Rope::with_physics(length, physics);
+    data.iter().for_each(|&motion| rope.apply(motion));
+    rope
 }
 
 pub fn calculate_tail_position((h2x, h2y): Vec2D, (tx, ty): Vec2D) -> Vec2D {
@@ -73,23 +204,12 @@ pub fn calculate_tail_position((h2x, h2y): Vec2D, (tx, ty): Vec2D) -> Vec2D {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
-
     use super::*;
     use test_case::test_case;
 
-    fn test_helper(input: &str, length: usize) -> (Vec<Vec<Vec2D>>, usize) {
+    fn test_helper(input: &str, length: usize) -> usize {
         let data = parse_input(input).unwrap();
-        let history = generate_motions(&data, length);
-
-        let mut visited: HashSet<Vec2D> = HashSet::new();
-        println!("{:?}", history);
-
-        history.iter().for_each(|v| {
-            visited.insert(*v.last().unwrap());
-        });
-
-        (history, visited.len())
+        simulate(&data, length).tail_visited().len()
     }
     mod part1 {
         use super::*;
@@ -104,9 +224,7 @@ D 1
 L 5
 R 2"#;
 
-            let (_history, results) = test_helper(input, 2);
-
-            // print_history(&_history);
+            let results = test_helper(input, 2);
 
             assert_eq!(results, 13);
         }
@@ -114,7 +232,7 @@ R 2"#;
         #[test]
         fn input_works() {
             let input = std::fs::read_to_string("input").unwrap();
-            let (_history, results) = test_helper(&input, 2);
+            let results = test_helper(&input, 2);
             assert_eq!(results, 6190);
         }
     }
@@ -132,12 +250,39 @@ R 17
 D 10
 L 25
 U 20";
-            let (_history, results) = test_helper(input, 10);
-
-            // print_history(&_history);
+            let results = test_helper(input, 10);
 
             assert_eq!(results, 36);
         }
+
+        #[test]
+        fn input_works() {
+            let input = std::fs::read_to_string("input").unwrap();
+            let results = test_helper(&input, 10);
+            assert_eq!(results, 2516);
+        }
+    }
+
+    #[test_case(2)]
+    #[test_case(10)]
+    fn rope_matches_history(length: usize) {
+        let data = parse_input(&std::fs::read_to_string("input").unwrap()).unwrap();
+        let history = generate_motions(&data, length);
+        let rope = simulate(&data, length);
+
+        assert_eq!(rope.knots(), history.last().unwrap().as_slice());
+        for knot in 0..length {
+            let visited = history.iter().map(|v| v[knot]).collect::<HashSet<_>>();
+            assert_eq!(rope.visited(knot), visited);
+        }
+    }
+
+    #[test_case((4, 0), vec![(1, 0); 4])]
+    #[test_case((0, -2), vec![(0, -1); 2])]
+    #[test_case((-2, 2), vec![(-1, 1); 2])]
+    #[test_case((0, 0), vec![])]
+    fn unit_steps_work(motion: Vec2D, expected: Vec<Vec2D>) {
+        assert_eq!(unit_steps(motion).collect::<Vec<_>>(), expected);
     }
 
     /// .....    .....    .....
@@ -215,6 +360,10 @@ U 20";
     #[test_case(5, "D 4", (0, 4))]
     #[test_case(6, "L 5", (-5, 0))]
     #[test_case(7, "R 2", (2, 0))]
+    #[test_case(8, "UL 3", (-3, -3))]
+    #[test_case(9, "DR 1", (1, 1))]
+    #[test_case(10, "UR 2", (2, -2))]
+    #[test_case(11, "DL 4", (-4, 4))]
     fn test_input_parser_success(_: usize, input: &str, v: Vec2D) {
         assert_eq!(parse_input_line(input).unwrap(), v);
     }
@@ -222,6 +371,8 @@ U 20";
     #[test_case(". 4")]
     #[test_case("asd1")]
     #[test_case("asadszxc")]
+    #[test_case("UD 1")]
+    #[test_case("LU 1")]
     #[test_case("R -5")]
     #[test_case("D abc")]
     #[test_case("")]
@@ -229,49 +380,45 @@ U 20";
         assert!(parse_input_line(line).is_err());
     }
 
+    #[test_case("F 2", (0, 0, 2))]
+    #[test_case("B 3", (0, 0, -3))]
+    #[test_case("UL 1", (-1, -1, 0))]
+    fn input_parser_3d_works(input: &str, v: Vec3D) {
+        assert_eq!(parse_input_line_3d(input).unwrap(), v);
+    }
+
+    #[test_case("F -1" ; "negative")]
+    #[test_case("FB 1" ; "unknown")]
+    fn input_parser_3d_failures(line: &str) {
+        assert!(parse_input_line_3d(line).is_err());
+    }
+
+    #[test]
+    fn flat_3d_rope_matches_2d() {
+        let input = std::fs::read_to_string("input").unwrap();
+        let rope = simulate(&parse_input_3d(&input).unwrap(), 10);
+        assert_eq!(rope.tail_visited().len(), 2516);
+    }
+
+    #[test]
+    fn rope_works_in_3d() {
+        let rope = simulate(&parse_input_3d("F 3\nR 2\nB 1").unwrap(), 3);
+        assert_eq!(rope.knots(), &[(2, 0, 2), (1, 0, 3), (1, 0, 2)]);
+        assert_eq!(
+            rope.tail_visited(),
+            HashSet::from([(0, 0, 0), (0, 0, 1), (1, 0, 2)])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "A rope needs at least one knot")]
+    fn empty_rope_fails() {
+        Rope::<Vec2D>::new(0);
+    }
+
     #[test_case( &[(0, 5), (0, 4), (0, 3)], &(0, 1), &[(0, 6), (0, 5), (0, 4)] )]
     #[test_case( &[(1, 0), (0, 1), (0, 2)], &(1, 0), &[(2, 0), (1, 0), (1, 1)] )]
     fn motion_gets_applied(initial: &[Vec2D], d: &Vec2D, expect: &[Vec2D]) {
         assert_eq!(apply_motion(initial, d), expect);
     }
-
-    // fn print_history(history: &[Vec<Vec2D>]) {
-    //     let (minx, maxx, miny, maxy) = history.iter().flatten().fold(
-    //         (
-    //             i32::max_value(),
-    //             i32::min_value(),
-    //             i32::max_value(),
-    //             i32::min_value(),
-    //         ),
-    //         |(minx, maxx, miny, maxy), (x, y)| {
-    //             (minx.min(*x), maxx.max(*x), miny.min(*y), maxy.max(*y))
-    //         },
-    //     );
-    //     println!("{minx}, {maxx}; {miny}, {maxy}");
-
-    //     history.iter().for_each(|frame| {
-    //         println!(
-    //             "{}\n",
-    //             (minx..maxx)
-    //                 .rev()
-    //                 .map(|i| {
-    //                     (miny..maxy)
-    //                         .map(|j| {
-    //                             match frame
-    //                                 .iter()
-    //                                 .enumerate()
-    //                                 .find(|&(_, &(x, y))| i == x && j == y)
-    //                             {
-    //                                 Some((0, _)) => 'H',
-    //                                 Some((idx, _)) => idx.to_string().chars().next().unwrap(),
-    //                                 None => '.',
-    //                             }
-    //                         })
-    //                         .collect::<String>()
-    //                 })
-    //                 .collect::<Vec<_>>()
-    //                 .join("\n")
-    //         );
-    //     });
-    // }
 }
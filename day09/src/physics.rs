@@ -0,0 +1,162 @@
+use crate::{Point, Vec2D};
+
+/// How a knot moves after the knot in front of it, its leader, has moved.
+#[derive(Clone, Copy, Debug)]
+pub enum FollowRule<P = Vec2D> {
+    /// Stays put until the leader is more than `L` cells away (in any direction, diagonals
+    /// included), then takes one step towards it. `Slack(1)` is the puzzle rule.
+    Slack(u32),
+    /// Like `Slack(1)`, but only ever steps along a single axis, the one with the largest gap
+    /// (the first such axis on ties).
+    Orthogonal,
+    /// Always ends up exactly `L` cells from the leader, on the line from the leader through the
+    /// knot's old position, so the rope is pushed as well as pulled. A knot on top of its leader
+    /// has no direction and stays put until the leader moves off.
+    Rigid(u32),
+    /// Any other rule, given the leader and the knot and returning the knot's new position. It
+    /// is only consulted after the leader has moved.
+    Custom(fn(P, P) -> P),
+}
+
+impl<P> Default for FollowRule<P> {
+    fn default() -> Self {
+        FollowRule::Slack(1)
+    }
+}
+
+impl<P: Point> FollowRule<P> {
+    pub fn follow(&self, leader: P, knot: P) -> P {
+        let offset = leader.sub(knot);
+        let distance = offset.chebyshev();
+        match *self {
+            FollowRule::Slack(slack) if distance > slack => knot.add(offset.map(i32::signum)),
+            FollowRule::Slack(_) => knot,
+            FollowRule::Orthogonal if distance <= 1 => knot,
+            FollowRule::Orthogonal => {
+                let axis = (0..P::AXES)
+                    .find(|&i| offset.axis(i).unsigned_abs() == distance)
+                    .unwrap();
+                knot.add(P::from_fn(|i| {
+                    if i == axis {
+                        offset.axis(i).signum()
+                    } else {
+                        0
+                    }
+                }))
+            }
+            FollowRule::Rigid(_) if distance == 0 => knot,
+            FollowRule::Rigid(length) => leader
+                .add(offset.map(|d| (-d as f64 * length as f64 / distance as f64).round() as i32)),
+            FollowRule::Custom(f) => f(leader, knot),
+        }
+    }
+}
+
+/// Rules for each knot behind the head: knot `k` uses `rules[k - 1]`, and knots past the end of
+/// the list use the last rule.
+#[derive(Clone, Debug)]
+pub struct Physics<P = Vec2D> {
+    pub rules: Vec<FollowRule<P>>,
+}
+
+impl<P> Default for Physics<P> {
+    fn default() -> Self {
+        Physics::uniform(FollowRule::default())
+    }
+}
+
+impl<P> Physics<P> {
+    pub fn uniform(rule: FollowRule<P>) -> Self {
+        Physics { rules: vec![rule] }
+    }
+
+    pub fn rule(&self, knot: usize) -> FollowRule<P>
+    where
+        P: Copy,
+    {
+        self.rules
+            .get(knot.saturating_sub(1))
+            .or(self.rules.last())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, simulate, simulate_with, Vec3D};
+    use test_case::test_case;
+
+    #[test_case(FollowRule::Slack(2), (3, 1), (0, 0), (1, 1) ; "slack pulls")]
+    #[test_case(FollowRule::Slack(2), (2, -2), (0, 0), (0, 0) ; "slack holds")]
+    #[test_case(FollowRule::Orthogonal, (2, 1), (0, 0), (1, 0) ; "orthogonal x")]
+    #[test_case(FollowRule::Orthogonal, (1, -2), (0, 0), (0, -1) ; "orthogonal y")]
+    #[test_case(FollowRule::Orthogonal, (1, 1), (0, 0), (0, 0) ; "orthogonal touching")]
+    #[test_case(FollowRule::Rigid(2), (3, 0), (0, 0), (1, 0) ; "rigid pulls")]
+    #[test_case(FollowRule::Rigid(2), (1, 0), (0, 0), (-1, 0) ; "rigid pushes")]
+    #[test_case(FollowRule::Rigid(2), (3, 1), (0, 0), (1, 0) ; "rigid rounds")]
+    #[test_case(FollowRule::Rigid(2), (0, 0), (0, 0), (0, 0) ; "rigid on top")]
+    fn rules_work(rule: FollowRule, leader: Vec2D, knot: Vec2D, expected: Vec2D) {
+        assert_eq!(rule.follow(leader, knot), expected);
+    }
+
+    #[test_case(FollowRule::Slack(1), (2, 1, -1), (1, 1, -1) ; "slack diagonal pull")]
+    #[test_case(FollowRule::Slack(1), (0, 0, 2), (0, 0, 1) ; "slack straight pull")]
+    #[test_case(FollowRule::Slack(1), (1, -1, 1), (0, 0, 0) ; "slack corner neighbour")]
+    #[test_case(FollowRule::Orthogonal, (1, 2, -2), (0, 1, 0) ; "orthogonal")]
+    #[test_case(FollowRule::Rigid(1), (0, 3, 3), (0, 2, 2) ; "rigid")]
+    fn rules_work_in_3d(rule: FollowRule<Vec3D>, leader: Vec3D, expected: Vec3D) {
+        assert_eq!(rule.follow(leader, (0, 0, 0)), expected);
+    }
+
+    #[test]
+    fn slack_1_matches_puzzle() {
+        for leader in (-2..=2).flat_map(|x| (-2..=2).map(move |y| (x, y))) {
+            assert_eq!(
+                FollowRule::Slack(1).follow(leader, (0, 0)),
+                crate::calculate_tail_position(leader, (0, 0))
+            );
+        }
+    }
+
+    #[test]
+    fn custom_rule_works() {
+        let physics = Physics::uniform(FollowRule::Custom(|leader, _| leader));
+        let rope = simulate_with(&parse_input("R 2\nU 1").unwrap(), 3, physics);
+        assert_eq!(rope.knots(), &[(2, -1); 3]);
+    }
+
+    #[test]
+    fn default_matches_puzzle() {
+        let data = parse_input(&std::fs::read_to_string("input").unwrap()).unwrap();
+        let rope = simulate_with(&data, 10, Physics::default());
+        assert_eq!(rope.tail_visited(), simulate(&data, 10).tail_visited());
+    }
+
+    #[test]
+    fn per_knot_rules_work() {
+        let physics = Physics {
+            rules: vec![FollowRule::Slack(1), FollowRule::Slack(3)],
+        };
+        assert!(matches!(physics.rule(1), FollowRule::Slack(1)));
+        assert!(matches!(physics.rule(5), FollowRule::Slack(3)));
+
+        // the second knot trails one cell, the rest of the rope three cells each
+        let rope = simulate_with(&parse_input("R 10").unwrap(), 4, physics);
+        assert_eq!(rope.knots(), &[(10, 0), (9, 0), (6, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn rigid_rope_keeps_its_length() {
+        let rope = simulate_with(
+            &parse_input("R 4\nUL 3\nD 2").unwrap(),
+            3,
+            Physics::uniform(FollowRule::Rigid(2)),
+        );
+        for pair in rope.knots().windows(2) {
+            let (dx, dy) = (pair[0].0 - pair[1].0, pair[0].1 - pair[1].1);
+            assert_eq!(dx.abs().max(dy.abs()), 2);
+        }
+    }
+}
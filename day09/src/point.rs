@@ -0,0 +1,65 @@
+use std::{fmt::Debug, hash::Hash};
+
+pub type Vec2D = (i32, i32);
+pub type Vec3D = (i32, i32, i32);
+
+/// A grid position or offset the rope can move through, in any number of dimensions.
+pub trait Point: Copy + Debug + Eq + Hash {
+    const AXES: usize;
+
+    fn origin() -> Self;
+    fn axis(self, i: usize) -> i32;
+    fn from_fn(f: impl FnMut(usize) -> i32) -> Self;
+
+    fn map(self, mut f: impl FnMut(i32) -> i32) -> Self {
+        Self::from_fn(|i| f(self.axis(i)))
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self::from_fn(|i| self.axis(i) + other.axis(i))
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self::from_fn(|i| self.axis(i) - other.axis(i))
+    }
+
+    /// Number of king moves (diagonals included) needed to cover this offset.
+    fn chebyshev(self) -> u32 {
+        (0..Self::AXES)
+            .map(|i| self.axis(i).unsigned_abs())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Point for Vec2D {
+    const AXES: usize = 2;
+
+    fn origin() -> Self {
+        (0, 0)
+    }
+
+    fn axis(self, i: usize) -> i32 {
+        [self.0, self.1][i]
+    }
+
+    fn from_fn(mut f: impl FnMut(usize) -> i32) -> Self {
+        (f(0), f(1))
+    }
+}
+
+impl Point for Vec3D {
+    const AXES: usize = 3;
+
+    fn origin() -> Self {
+        (0, 0, 0)
+    }
+
+    fn axis(self, i: usize) -> i32 {
+        [self.0, self.1, self.2][i]
+    }
+
+    fn from_fn(mut f: impl FnMut(usize) -> i32) -> Self {
+        (f(0), f(1), f(2))
+    }
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{Point, Vec2D};
+
+/// Where a single knot has been. A visit is the knot arriving at a cell, so a knot that stays
+/// put while the head moves does not add to the count of its cell.
+#[derive(Clone, Debug)]
+pub struct KnotStats<P = Vec2D> {
+    pub visits: HashMap<P, usize>,
+    /// Unit step at which the knot first reached each cell, `0` being the starting position.
+    pub first_visit: HashMap<P, usize>,
+    /// Largest number of king moves between the knot and the origin.
+    pub max_distance: u32,
+}
+
+impl<P: Point> Default for KnotStats<P> {
+    fn default() -> Self {
+        let mut stats = KnotStats {
+            visits: HashMap::new(),
+            first_visit: HashMap::new(),
+            max_distance: 0,
+        };
+        stats.record(P::origin(), 0);
+        stats
+    }
+}
+
+impl<P: Point> KnotStats<P> {
+    pub fn record(&mut self, position: P, step: usize) {
+        *self.visits.entry(position).or_default() += 1;
+        self.first_visit.entry(position).or_insert(step);
+        self.max_distance = self.max_distance.max(position.chebyshev());
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.visits.len()
+    }
+
+    /// The cell visited most often, the first one reached on ties.
+    pub fn busiest(&self) -> Option<(P, usize)> {
+        self.visits
+            .iter()
+            .max_by_key(|&(p, &count)| (count, std::cmp::Reverse(self.first_visit[p])))
+            .map(|(&p, &count)| (p, count))
+    }
+}
+
+impl KnotStats<Vec2D> {
+    /// Visit counts as a grid over the visited area, with the `x` coordinates in the header and
+    /// the `y` coordinate leading each row.
+    pub fn to_csv(&self) -> String {
+        let (min_x, max_x, min_y, max_y) =
+            self.visits
+                .keys()
+                .fold((0, 0, 0, 0), |(min_x, max_x, min_y, max_y), &(x, y)| {
+                    (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+                });
+
+        let mut csv = String::from("y\\x");
+        for x in min_x..=max_x {
+            write!(csv, ",{x}").unwrap();
+        }
+        for y in min_y..=max_y {
+            write!(csv, "\n{y}").unwrap();
+            for x in min_x..=max_x {
+                write!(csv, ",{}", self.visits.get(&(x, y)).unwrap_or(&0)).unwrap();
+            }
+        }
+        csv.push('\n');
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_input, simulate};
+
+    const EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+
+    #[test]
+    fn example_stats_work() {
+        let rope = simulate(&parse_input(EXAMPLE).unwrap(), 2);
+
+        let head = rope.stats(0);
+        assert_eq!(head.distinct(), 21);
+        assert_eq!(head.max_distance, 5);
+        assert_eq!(head.first_visit[&(4, -4)], 8);
+        assert_eq!(head.busiest(), Some(((4, -2), 2)));
+
+        let tail = rope.stats(1);
+        assert_eq!(tail.distinct(), 13);
+        assert_eq!(tail.visits.values().sum::<usize>(), 14);
+        assert_eq!(tail.first_visit[&(1, -2)], 22);
+    }
+
+    #[test]
+    fn csv_works() {
+        let rope = simulate(&parse_input("R 2\nL 3\nU 1").unwrap(), 1);
+        assert_eq!(
+            rope.stats(0).to_csv(),
+            "y\\x,-1,0,1,2\n-1,1,0,0,0\n0,1,2,2,1\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{
+    isa::{Opcode, Register},
+    Instruction,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownInstruction(String),
+    OperandCount {
+        mnemonic: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber(String),
+    UnknownRegister(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnknownInstruction(s) => write!(f, "unknown instruction '{s}'"),
+            ParseErrorKind::OperandCount {
+                mnemonic,
+                expected,
+                found,
+            } => write!(f, "{mnemonic} takes {expected} operand(s), found {found}"),
+            ParseErrorKind::InvalidNumber(s) => write!(f, "invalid number '{s}'"),
+            ParseErrorKind::UnknownRegister(s) => write!(f, "unknown register '{s}'"),
+            ParseErrorKind::UnknownLabel(s) => write!(f, "unknown label '{s}'"),
+            ParseErrorKind::DuplicateLabel(s) => write!(f, "label '{s}' is defined twice"),
+        }
+    }
+}
+
+/// `line` starts at 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+/// Parses one instruction, looking jump targets up in `labels` or reading them as instruction
+/// indices.
+pub(crate) fn parse_line(
+    line: &str,
+    labels: &HashMap<&str, usize>,
+) -> Result<Instruction, ParseErrorKind> {
+    let mut words = line.split_whitespace();
+    let mnemonic = words.next().unwrap_or_default();
+    let opcode = Opcode::from_mnemonic(mnemonic)
+        .ok_or_else(|| ParseErrorKind::UnknownInstruction(mnemonic.to_string()))?;
+    let info = opcode.info();
+
+    let operands = words.collect::<Vec<_>>();
+    if operands.len() != info.operands.len() {
+        return Err(ParseErrorKind::OperandCount {
+            mnemonic: info.mnemonic,
+            expected: info.operands.len(),
+            found: operands.len(),
+        });
+    }
+    let number = |s: &str| {
+        s.parse::<i32>()
+            .map_err(|_| ParseErrorKind::InvalidNumber(s.to_string()))
+    };
+
+    Ok(match opcode {
+        Opcode::Noop => Instruction::Noop,
+        Opcode::AddX => Instruction::AddX(number(operands[0])?),
+        Opcode::AddY => Instruction::AddY(number(operands[0])?),
+        Opcode::Mul => Instruction::Mul(number(operands[0])?),
+        Opcode::Jnz => {
+            let register = match operands[0] {
+                "x" => Register::X,
+                "y" => Register::Y,
+                s => return Err(ParseErrorKind::UnknownRegister(s.to_string())),
+            };
+            let target = match labels.get(operands[1]) {
+                Some(&target) => target,
+                None => operands[1]
+                    .parse()
+                    .map_err(|_| ParseErrorKind::UnknownLabel(operands[1].to_string()))?,
+            };
+            Instruction::Jnz(register, target)
+        }
+    })
+}
+
+/// Assembles a program with one instruction per line. A line such as `loop:` defines a label
+/// pointing at the instruction after it, which `jnz` can then use as its target. Blank lines
+/// are skipped.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, ParseError> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let mut labels = HashMap::new();
+    let mut address = 0;
+    for (line, text) in lines.clone() {
+        match text.strip_suffix(':') {
+            Some(label) => {
+                if labels.insert(label, address).is_some() {
+                    return Err(ParseError {
+                        line,
+                        kind: ParseErrorKind::DuplicateLabel(label.to_string()),
+                    });
+                }
+            }
+            None => address += 1,
+        }
+    }
+
+    lines
+        .filter(|(_, text)| !text.ends_with(':'))
+        .map(|(line, text)| parse_line(text, &labels).map_err(|kind| ParseError { line, kind }))
+        .collect()
+}
+
+/// Turns a program back into source, naming every jump target `l<index>`. Targets past the end
+/// of the program have nowhere to put a label and stay numeric.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let targets = program
+        .iter()
+        .filter_map(|i| match i {
+            Instruction::Jnz(_, target) if *target <= program.len() => Some(*target),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut source = String::new();
+    for address in 0..=program.len() {
+        if targets.contains(&address) {
+            source += &format!("l{address}:\n");
+        }
+        match program.get(address) {
+            Some(Instruction::Jnz(register, target)) if targets.contains(target) => {
+                source += &format!("jnz {} l{target}\n", register.name())
+            }
+            Some(instruction) => source += &format!("{instruction}\n"),
+            None => {}
+        }
+    }
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Instruction as I;
+    use test_case::test_case;
+
+    const COUNTDOWN: &str = "addy 3
+loop:
+  addx 2
+  addy -1
+  jnz y loop
+mul 10
+end:";
+
+    #[test]
+    fn labels_are_resolved() {
+        assert_eq!(
+            assemble(COUNTDOWN).unwrap(),
+            vec![
+                I::AddY(3),
+                I::AddX(2),
+                I::AddY(-1),
+                I::Jnz(Register::Y, 1),
+                I::Mul(10)
+            ]
+        );
+        assert_eq!(
+            assemble("jnz x end\nnoop\nend:").unwrap(),
+            vec![I::Jnz(Register::X, 2), I::Noop]
+        );
+    }
+
+    #[test]
+    fn disassemble_round_trips() {
+        let program = assemble(COUNTDOWN).unwrap();
+        let source = disassemble(&program);
+        assert_eq!(source, "addy 3\nl1:\naddx 2\naddy -1\njnz y l1\nmul 10\n");
+        assert_eq!(assemble(&source).unwrap(), program);
+
+        let example = assemble(include_str!("../example")).unwrap();
+        assert_eq!(assemble(&disassemble(&example)).unwrap(), example);
+
+        let past_the_end = assemble("jnz x 5\nnoop").unwrap();
+        let source = disassemble(&past_the_end);
+        assert_eq!(source, "jnz x 5\nnoop\n");
+        assert_eq!(assemble(&source).unwrap(), past_the_end);
+    }
+
+    #[test_case("noop\nfoo 1", 2, ParseErrorKind::UnknownInstruction("foo".to_string()) ; "unknown")]
+    #[test_case("noop\n\naddx", 3, ParseErrorKind::OperandCount { mnemonic: "addx", expected: 1, found: 0 } ; "count")]
+    #[test_case("mul two", 1, ParseErrorKind::InvalidNumber("two".to_string()) ; "number")]
+    #[test_case("jnz z 0", 1, ParseErrorKind::UnknownRegister("z".to_string()) ; "register")]
+    #[test_case("jnz x nowhere", 1, ParseErrorKind::UnknownLabel("nowhere".to_string()) ; "label")]
+    #[test_case("a:\nnoop\na:", 3, ParseErrorKind::DuplicateLabel("a".to_string()) ; "duplicate")]
+    fn errors_have_line_numbers(source: &str, line: usize, kind: ParseErrorKind) {
+        assert_eq!(assemble(source), Err(ParseError { line, kind }));
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(
+            assemble("noop\naddx 1 2").unwrap_err().to_string(),
+            "line 2: addx takes 1 operand(s), found 2"
+        );
+    }
+}
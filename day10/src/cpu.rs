@@ -0,0 +1,257 @@
+use crate::Instruction;
+
+/// 32 bit registers; arithmetic wraps around on overflow rather than failing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Registers {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers { x: 1, y: 0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pipeline {
+    /// The next cycle starts by fetching an instruction.
+    Fetch,
+    /// `remaining` cycles are left before `instruction`, the `pc`-th of the program, completes.
+    Execute {
+        instruction: Instruction,
+        pc: usize,
+        remaining: u32,
+    },
+}
+
+/// The machine state during one cycle. Registers hold the values seen in the middle of the
+/// cycle, before the instruction completing in it takes effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tick {
+    /// Starts at 1.
+    pub cycle: usize,
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub registers: Registers,
+}
+
+/// Something watching the CPU, called once for every cycle.
+pub trait Observer {
+    fn tick(&mut self, tick: &Tick);
+}
+
+impl<F: FnMut(&Tick)> Observer for F {
+    fn tick(&mut self, tick: &Tick) {
+        self(tick)
+    }
+}
+
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn tick(&mut self, tick: &Tick) {
+        self.0.tick(tick);
+        self.1.tick(tick);
+    }
+}
+
+/// Instructions are kept once fetched, so jumps can go back as well as forward.
+#[derive(Clone, Debug)]
+pub struct Cpu<I> {
+    pub registers: Registers,
+    /// Number of cycles completed so far.
+    pub cycle: usize,
+    pub pipeline: Pipeline,
+    /// Index of the next instruction to fetch.
+    pub pc: usize,
+    program: I,
+    memory: Vec<Instruction>,
+}
+
+impl<I: Iterator<Item = Instruction>> Cpu<I> {
+    pub fn new(program: impl IntoIterator<IntoIter = I>) -> Self {
+        Cpu {
+            registers: Registers::default(),
+            cycle: 0,
+            pipeline: Pipeline::Fetch,
+            pc: 0,
+            program: program.into_iter(),
+            memory: vec![],
+        }
+    }
+
+    fn fetch(&mut self) -> Option<Instruction> {
+        while self.memory.len() <= self.pc {
+            self.memory.push(self.program.next()?);
+        }
+        Some(self.memory[self.pc])
+    }
+
+    /// Runs a single cycle, fetching the next instruction first when the previous one is done.
+    /// Returns `None` once the program has run out.
+    pub fn step(&mut self) -> Option<Tick> {
+        if self.pipeline == Pipeline::Fetch {
+            let instruction = self.fetch()?;
+            self.pipeline = Pipeline::Execute {
+                instruction,
+                pc: self.pc,
+                remaining: instruction.cycles(),
+            };
+            self.pc += 1;
+        }
+        let Pipeline::Execute {
+            instruction,
+            pc,
+            remaining,
+        } = &mut self.pipeline
+        else {
+            unreachable!()
+        };
+
+        self.cycle += 1;
+        let tick = Tick {
+            cycle: self.cycle,
+            pc: *pc,
+            instruction: *instruction,
+            registers: self.registers,
+        };
+
+        *remaining -= 1;
+        if *remaining == 0 {
+            match *instruction {
+                Instruction::Noop => {}
+                Instruction::AddX(x) => self.registers.x = self.registers.x.wrapping_add(x),
+                Instruction::AddY(y) => self.registers.y = self.registers.y.wrapping_add(y),
+                Instruction::Mul(n) => self.registers.x = self.registers.x.wrapping_mul(n),
+                Instruction::Jnz(register, target) => {
+                    if register.get(&self.registers) != 0 {
+                        self.pc = target;
+                    }
+                }
+            }
+            self.pipeline = Pipeline::Fetch;
+        }
+        Some(tick)
+    }
+
+    pub fn run(&mut self, observer: &mut impl Observer) {
+        while let Some(tick) = self.step() {
+            observer.tick(&tick);
+        }
+    }
+}
+
+/// Every tick, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace(pub Vec<Tick>);
+
+impl Observer for Trace {
+    fn tick(&mut self, tick: &Tick) {
+        self.0.push(*tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse_instruction,
+        sampling::{Sampled, SamplingSchedule, SignalSum},
+        Instruction as I,
+    };
+
+    #[test]
+    fn small_program_works() {
+        let mut cpu = Cpu::new([I::Noop, I::AddX(3), I::AddX(-5)]);
+        let mut trace = Trace::default();
+        cpu.run(&mut trace);
+
+        assert_eq!(
+            trace
+                .0
+                .iter()
+                .map(|t| (t.cycle, t.pc, t.registers.x))
+                .collect::<Vec<_>>(),
+            vec![(1, 0, 1), (2, 1, 1), (3, 1, 1), (4, 2, 4), (5, 2, 4)]
+        );
+        assert_eq!(cpu.registers.x, -1);
+        assert_eq!(cpu.cycle, 5);
+        assert_eq!(cpu.pipeline, Pipeline::Fetch);
+    }
+
+    #[test]
+    fn step_exposes_the_pipeline() {
+        let mut cpu = Cpu::new([I::AddX(2)]);
+        cpu.step();
+        assert_eq!(
+            cpu.pipeline,
+            Pipeline::Execute {
+                instruction: I::AddX(2),
+                pc: 0,
+                remaining: 1
+            }
+        );
+        assert_eq!(cpu.step().unwrap().registers.x, 1);
+        assert_eq!(cpu.registers.x, 3);
+        assert_eq!(cpu.step(), None);
+    }
+
+    #[test]
+    fn jumps_work() {
+        let program = crate::asm::assemble(
+            "addy 3
+loop:
+addx 2
+addy -1
+jnz y loop
+mul 10",
+        )
+        .unwrap();
+        let mut cpu = Cpu::new(program);
+        let mut trace = Trace::default();
+        cpu.run(&mut trace);
+
+        assert_eq!(cpu.registers, Registers { x: 70, y: 0 });
+        // 2 + 3 * (2 + 2 + 2) + 3
+        assert_eq!(cpu.cycle, 23);
+        assert_eq!(
+            trace.0.iter().map(|t| t.pc).collect::<Vec<_>>(),
+            [[0, 0].as_slice(), &[1, 1, 2, 2, 3, 3].repeat(3), &[4, 4, 4]].concat()
+        );
+    }
+
+    #[test]
+    fn arithmetic_wraps() {
+        let program = crate::asm::assemble("mul 100000\nmul 100000\naddy 2147483647\naddy 1");
+        let mut cpu = Cpu::new(program.unwrap());
+        cpu.run(&mut Trace::default());
+
+        // 10^10 - 2 * 2^32
+        assert_eq!(
+            cpu.registers,
+            Registers {
+                x: 1410065408,
+                y: i32::MIN
+            }
+        );
+    }
+
+    #[test]
+    fn observers_combine() {
+        let program = include_str!("../example")
+            .lines()
+            .map(|l| parse_instruction(l).unwrap())
+            .collect::<Vec<_>>();
+        let mut cycles = 0;
+        let mut observers = (
+            Sampled {
+                schedule: SamplingSchedule::puzzle(),
+                observer: SignalSum::default(),
+            },
+            |_: &Tick| cycles += 1,
+        );
+        Cpu::new(program).run(&mut observers);
+
+        assert_eq!(observers.0.observer.total, 13140);
+        assert_eq!(cycles, 240);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{fmt, str::FromStr};
+
+use crate::cpu::{Observer, Tick};
+
+pub const WIDTH: usize = 40;
+pub const HEIGHT: usize = 6;
+
+/// The letters the CRT is known to draw, 4 pixels wide and 6 high, row by row.
+const GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// A 40x6 screen whose beam draws one pixel per cycle, left to right and top to bottom. A pixel
+/// is lit when the 3 pixel wide sprite centred on X covers the beam.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crt {
+    pub pixels: [[bool; WIDTH]; HEIGHT],
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            pixels: [[false; WIDTH]; HEIGHT],
+        }
+    }
+}
+
+/// `(row, column)` of the beam during a cycle, wrapping around after the last pixel.
+pub fn beam(cycle: usize) -> (usize, usize) {
+    let position = (cycle - 1) % (WIDTH * HEIGHT);
+    (position / WIDTH, position % WIDTH)
+}
+
+impl Observer for Crt {
+    fn tick(&mut self, tick: &Tick) {
+        let (row, col) = beam(tick.cycle);
+        self.pixels[row][col] = tick.registers.x.abs_diff(col as i32) <= 1;
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.pixels.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for &lit in row {
+                write!(f, "{}", if lit { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The text form produced by `Display`, `#` for lit pixels and `.` for dark ones.
+impl FromStr for Crt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut crt = Crt::default();
+        let rows = s.lines().collect::<Vec<_>>();
+        if rows.len() != HEIGHT {
+            return Err(format!("Expected {HEIGHT} rows, found {}", rows.len()));
+        }
+        for (pixels, row) in crt.pixels.iter_mut().zip(rows) {
+            if row.chars().count() != WIDTH {
+                return Err(format!("Expected rows of {WIDTH} pixels: {row}"));
+            }
+            for (pixel, c) in pixels.iter_mut().zip(row.chars()) {
+                *pixel = match c {
+                    '#' => true,
+                    '.' => false,
+                    _ => return Err(format!("Unexpected pixel {c}")),
+                };
+            }
+        }
+        Ok(crt)
+    }
+}
+
+impl Crt {
+    /// Binary `P6` PPM, every pixel a `scale` x `scale` square.
+    pub fn to_ppm(&self, scale: usize) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", WIDTH * scale, HEIGHT * scale).into_bytes();
+        for row in &self.pixels {
+            for _ in 0..scale {
+                for &lit in row {
+                    let colour = if lit { [255, 200, 40] } else { [15, 15, 35] };
+                    (0..scale).for_each(|_| out.extend(colour));
+                }
+            }
+        }
+        out
+    }
+
+    /// Draws up to 8 letters the way `decode` reads them.
+    pub fn from_letters(text: &str) -> Result<Crt, String> {
+        let mut crt = Crt::default();
+        if text.chars().count() > WIDTH / 5 {
+            return Err(format!("At most {} letters fit", WIDTH / 5));
+        }
+        for (i, c) in text.chars().enumerate() {
+            let (_, glyph) = GLYPHS
+                .iter()
+                .find(|&&(g, _)| g == c)
+                .ok_or_else(|| format!("No glyph for {c}"))?;
+            for (j, pixel) in glyph.chars().enumerate() {
+                crt.pixels[j / 4][i * 5 + j % 4] = pixel == '#';
+            }
+        }
+        Ok(crt)
+    }
+
+    /// Reads the 8 letters on the screen, each 4 pixels wide with a blank column after it. Empty
+    /// spaces read as ` `.
+    pub fn decode(&self) -> Result<String, String> {
+        (0..WIDTH / 5)
+            .map(|i| {
+                let glyph = self
+                    .pixels
+                    .iter()
+                    .flat_map(|row| &row[i * 5..i * 5 + 4])
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .collect::<String>();
+                if !glyph.contains('#') {
+                    return Ok(' ');
+                }
+                GLYPHS
+                    .iter()
+                    .find(|(_, pattern)| *pattern == glyph)
+                    .map(|&(c, _)| c)
+                    .ok_or_else(|| format!("Unknown glyph at column {}", i * 5))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu::Cpu, parse_instruction};
+
+    fn render(input: &str) -> Crt {
+        let program = input.lines().map(|l| parse_instruction(l).unwrap());
+        let mut crt = Crt::default();
+        Cpu::new(program).run(&mut crt);
+        crt
+    }
+
+    #[test]
+    fn example_works() {
+        assert_eq!(
+            render(include_str!("../example")).to_string(),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
+
+    #[test]
+    fn input_works() {
+        let crt = render(&std::fs::read_to_string("input").unwrap());
+        assert_eq!(crt.decode().unwrap(), "RKPJBPLA");
+    }
+
+    #[test]
+    fn unknown_glyphs_fail() {
+        assert_eq!(
+            render(include_str!("../example")).decode(),
+            Err("Unknown glyph at column 0".to_string())
+        );
+    }
+
+    #[test]
+    fn extreme_x_is_dark() {
+        // X ends up at i32::MIN
+        let crt = render("addx -2147483647\naddx -2\nnoop\nnoop");
+        assert_eq!(crt.pixels[0][..6], [true, true, false, false, false, false]);
+    }
+
+    #[test]
+    fn ppm_works() {
+        let ppm = Crt::default().to_ppm(2);
+        assert!(ppm.starts_with(b"P6\n80 12\n255\n"));
+        assert_eq!(ppm.len(), 13 + 80 * 12 * 3);
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let crt = render(include_str!("../example"));
+        assert_eq!(crt.to_string().parse::<Crt>(), Ok(crt));
+        assert!("#.".parse::<Crt>().is_err());
+        assert!(".\n".repeat(6).parse::<Crt>().is_err());
+        assert!("x".repeat(40).repeat(6).parse::<Crt>().is_err());
+    }
+
+    #[test]
+    fn letters_round_trip() {
+        let text = GLYPHS.iter().map(|&(c, _)| c).collect::<String>();
+        for letters in [&text[..8], &text[8..16], &text[16..]] {
+            let crt = Crt::from_letters(letters).unwrap();
+            assert_eq!(crt.decode().unwrap().trim_end(), letters);
+        }
+        assert!(Crt::from_letters("abc").is_err());
+        assert!(Crt::from_letters("AAAAAAAAA").is_err());
+    }
+
+    #[test]
+    fn glyphs_are_4_by_6() {
+        assert!(GLYPHS.iter().all(|(_, g)| g.len() == 24));
+    }
+}
@@ -0,0 +1,368 @@
+use std::{fmt::Write as _, str::FromStr};
+
+use crate::{
+    cpu::{Cpu, Pipeline, Tick},
+    Instruction,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakpoint {
+    /// Before the given cycle runs.
+    Cycle(usize),
+    /// Before the instruction at the given index is fetched.
+    Instruction(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    /// X changed during the last cycle.
+    Watch {
+        old: i32,
+        new: i32,
+    },
+    Steps,
+    /// `continue` ran `cycle_limit` cycles without anything else stopping it.
+    Limit,
+    Halted,
+}
+
+/// A line of a debugger script.
+///
+/// `break cycle <n>`, `break pc <n>`, `delete cycle <n>`, `delete pc <n>`, `watch x`,
+/// `unwatch x`, `step [<n>]`, `continue`, `limit <n>`, `print` and `history [<n>]`. Blank lines
+/// and lines starting with `#` are ignored by `run_script`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Break(Breakpoint),
+    Delete(Breakpoint),
+    Watch(bool),
+    Step(usize),
+    Continue,
+    /// Sets the number of cycles a single `continue` may run.
+    Limit(usize),
+    Print,
+    History(Option<usize>),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .map_err(|_| format!("Invalid number {n}"))
+        };
+        let breakpoint = |kind: &str, n: &str| match kind {
+            "cycle" => Ok(Breakpoint::Cycle(number(n)?)),
+            "pc" => Ok(Breakpoint::Instruction(number(n)?)),
+            _ => Err(format!("Unknown breakpoint kind {kind}")),
+        };
+
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["break", kind, n] => Ok(Command::Break(breakpoint(kind, n)?)),
+            ["delete", kind, n] => Ok(Command::Delete(breakpoint(kind, n)?)),
+            ["watch", "x"] => Ok(Command::Watch(true)),
+            ["unwatch", "x"] => Ok(Command::Watch(false)),
+            ["step"] => Ok(Command::Step(1)),
+            ["step", n] => Ok(Command::Step(number(n)?)),
+            ["continue"] => Ok(Command::Continue),
+            ["limit", n] => Ok(Command::Limit(number(n)?)),
+            ["print"] => Ok(Command::Print),
+            ["history"] => Ok(Command::History(None)),
+            ["history", n] => Ok(Command::History(Some(number(n)?))),
+            _ => Err(format!("Unknown command {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Debugger<I> {
+    pub cpu: Cpu<I>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watch_x: bool,
+    /// Every cycle run so far.
+    pub history: Vec<Tick>,
+    /// Most cycles a single `cont` runs, so looping programs still return.
+    pub cycle_limit: usize,
+    /// Cycles completed when `cont` last stopped, and the breakpoints reported since then.
+    stopped_on: (usize, Vec<Breakpoint>),
+}
+
+impl<I: Iterator<Item = Instruction>> Debugger<I> {
+    pub fn new(program: impl IntoIterator<IntoIter = I>) -> Self {
+        Debugger {
+            cpu: Cpu::new(program),
+            breakpoints: vec![],
+            watch_x: false,
+            history: vec![],
+            cycle_limit: 1_000_000,
+            stopped_on: (0, vec![]),
+        }
+    }
+
+    /// Index of the instruction the next cycle belongs to.
+    fn current_pc(&self) -> usize {
+        match self.cpu.pipeline {
+            Pipeline::Execute { pc, .. } => pc,
+            Pipeline::Fetch => self.cpu.pc,
+        }
+    }
+
+    /// The breakpoint before the upcoming cycle, other than the ones already stopped on there.
+    fn breakpoint_hit(&self) -> Option<Breakpoint> {
+        let (stopped, reported) = &self.stopped_on;
+        self.breakpoints.iter().copied().find(|&b| match b {
+            _ if *stopped == self.cpu.cycle && reported.contains(&b) => false,
+            Breakpoint::Cycle(cycle) => self.cpu.cycle + 1 == cycle,
+            Breakpoint::Instruction(pc) => {
+                self.cpu.pipeline == Pipeline::Fetch && self.cpu.pc == pc
+            }
+        })
+    }
+
+    /// Runs one cycle, or returns `Stop::Halted` when the program is done.
+    fn cycle(&mut self) -> Result<(), Stop> {
+        let old = self.cpu.registers.x;
+        let tick = self.cpu.step().ok_or(Stop::Halted)?;
+        self.history.push(tick);
+        match self.cpu.registers.x {
+            new if self.watch_x && new != old => Err(Stop::Watch { old, new }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs up to `n` cycles, stopping early only when the program halts.
+    pub fn step(&mut self, n: usize) -> Stop {
+        for _ in 0..n {
+            if let Err(Stop::Halted) = self.cycle() {
+                return Stop::Halted;
+            }
+        }
+        Stop::Steps
+    }
+
+    /// Runs until a breakpoint or watchpoint triggers, or `cycle_limit` cycles have run.
+    /// Breakpoints are checked before every cycle, so the first cycle can be stopped on too.
+    pub fn cont(&mut self) -> Stop {
+        for _ in 0..self.cycle_limit {
+            if let Some(breakpoint) = self.breakpoint_hit() {
+                if self.stopped_on.0 != self.cpu.cycle {
+                    self.stopped_on = (self.cpu.cycle, vec![]);
+                }
+                self.stopped_on.1.push(breakpoint);
+                return Stop::Breakpoint(breakpoint);
+            }
+            if let Err(stop) = self.cycle() {
+                return stop;
+            }
+        }
+        Stop::Limit
+    }
+
+    /// The upcoming cycle and the registers it will see.
+    pub fn state(&self) -> String {
+        let registers = self.cpu.registers;
+        format!(
+            "cycle {}, pc {}: x={} y={}",
+            self.cpu.cycle + 1,
+            self.current_pc(),
+            registers.x,
+            registers.y
+        )
+    }
+
+    /// Runs a command and returns what it prints.
+    pub fn execute(&mut self, command: Command) -> String {
+        let stopped = |debugger: &Self, stop| {
+            let reason = match stop {
+                Stop::Breakpoint(Breakpoint::Cycle(n)) => format!("breakpoint at cycle {n}"),
+                Stop::Breakpoint(Breakpoint::Instruction(n)) => format!("breakpoint at pc {n}"),
+                Stop::Watch { old, new } => format!("x changed from {old} to {new}"),
+                Stop::Steps => return debugger.state() + "\n",
+                Stop::Limit => format!("no stop after {} cycles", debugger.cycle_limit),
+                Stop::Halted => "halted".to_string(),
+            };
+            format!("{reason}\n{}\n", debugger.state())
+        };
+
+        match command {
+            Command::Break(breakpoint) => {
+                if !self.breakpoints.contains(&breakpoint) {
+                    self.breakpoints.push(breakpoint);
+                }
+                String::new()
+            }
+            Command::Delete(breakpoint) => {
+                self.breakpoints.retain(|&b| b != breakpoint);
+                String::new()
+            }
+            Command::Watch(watch) => {
+                self.watch_x = watch;
+                String::new()
+            }
+            Command::Step(n) => {
+                let stop = self.step(n);
+                stopped(self, stop)
+            }
+            Command::Continue => {
+                let stop = self.cont();
+                stopped(self, stop)
+            }
+            Command::Limit(n) => {
+                self.cycle_limit = n;
+                String::new()
+            }
+            Command::Print => self.state() + "\n",
+            Command::History(n) => {
+                let skip = n.map_or(0, |n| self.history.len().saturating_sub(n));
+                self.history[skip..]
+                    .iter()
+                    .fold(String::new(), |mut out, t| {
+                        writeln!(
+                            out,
+                            "cycle {}, pc {}: x={} y={}",
+                            t.cycle, t.pc, t.registers.x, t.registers.y
+                        )
+                        .unwrap();
+                        out
+                    })
+            }
+        }
+    }
+
+    /// Runs every command of a script and returns everything printed.
+    pub fn run_script(&mut self, script: &str) -> Result<String, String> {
+        let mut out = String::new();
+        for (i, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let command = line.parse().map_err(|e| format!("line {}: {e}", i + 1))?;
+            out += &self.execute(command);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use test_case::test_case;
+
+    fn example() -> Debugger<std::vec::IntoIter<Instruction>> {
+        Debugger::new(assemble(include_str!("../example")).unwrap())
+    }
+
+    #[test]
+    fn breakpoints_on_cycles_work() {
+        let mut debugger = example();
+        let out = debugger
+            .run_script(
+                "break cycle 20
+                 break cycle 60
+                 continue
+                 continue
+                 delete cycle 60
+                 continue",
+            )
+            .unwrap();
+        assert_eq!(
+            out,
+            "breakpoint at cycle 20
+cycle 20, pc 10: x=21 y=0
+breakpoint at cycle 60
+cycle 60, pc 32: x=19 y=0
+halted
+cycle 241, pc 146: x=17 y=0
+"
+        );
+    }
+
+    #[test]
+    fn breakpoints_on_instructions_work() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\naddx -5").unwrap());
+        let out = debugger.run_script("break pc 2\ncontinue\nprint").unwrap();
+        assert_eq!(
+            out,
+            "breakpoint at pc 2\ncycle 4, pc 2: x=4 y=0\ncycle 4, pc 2: x=4 y=0\n"
+        );
+    }
+
+    #[test_case("break cycle 1", "breakpoint at cycle 1" ; "cycle")]
+    #[test_case("break pc 0", "breakpoint at pc 0" ; "pc")]
+    fn breakpoints_before_the_first_cycle_work(command: &str, reason: &str) {
+        let mut debugger = Debugger::new(assemble("addx 3\nnoop").unwrap());
+        let out = debugger
+            .run_script(&format!("{command}\ncontinue\ncontinue"))
+            .unwrap();
+        assert_eq!(
+            out,
+            format!("{reason}\ncycle 1, pc 0: x=1 y=0\nhalted\ncycle 4, pc 2: x=4 y=0\n")
+        );
+    }
+
+    #[test]
+    fn breakpoints_at_the_same_place_all_stop() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\nnoop").unwrap());
+        debugger.run_script("break pc 1\nbreak cycle 2").unwrap();
+        assert_eq!(
+            debugger.cont(),
+            Stop::Breakpoint(Breakpoint::Instruction(1))
+        );
+        assert_eq!(debugger.cont(), Stop::Breakpoint(Breakpoint::Cycle(2)));
+        assert_eq!(debugger.cont(), Stop::Halted);
+    }
+
+    #[test]
+    fn loops_hit_the_cycle_limit() {
+        let mut debugger = Debugger::new(assemble("l:\njnz x l").unwrap());
+        let out = debugger.run_script("limit 10\ncontinue\ncontinue").unwrap();
+        assert_eq!(
+            out,
+            "no stop after 10 cycles
+cycle 11, pc 0: x=1 y=0
+no stop after 10 cycles
+cycle 21, pc 0: x=1 y=0
+"
+        );
+    }
+
+    #[test]
+    fn stepping_and_history_work() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\naddx -5").unwrap());
+        let out = debugger
+            .run_script("# the first two instructions\nstep 3\nhistory 2\nstep 5")
+            .unwrap();
+        assert_eq!(
+            out,
+            "cycle 4, pc 2: x=4 y=0
+cycle 2, pc 1: x=1 y=0
+cycle 3, pc 1: x=1 y=0
+halted
+cycle 6, pc 3: x=-1 y=0
+"
+        );
+        assert_eq!(debugger.history.len(), 5);
+    }
+
+    #[test]
+    fn watchpoints_work() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\nnoop\naddx -5").unwrap());
+        assert_eq!(debugger.run_script("watch x").unwrap(), "");
+        assert_eq!(debugger.cont(), Stop::Watch { old: 1, new: 4 });
+        assert_eq!(debugger.cpu.cycle, 3);
+        assert_eq!(debugger.cont(), Stop::Watch { old: 4, new: -1 });
+        debugger.execute(Command::Watch(false));
+        assert_eq!(debugger.cont(), Stop::Halted);
+    }
+
+    #[test_case("jump 3", "line 1: Unknown command jump 3" ; "unknown")]
+    #[test_case("\nbreak cycle x", "line 2: Invalid number x" ; "number")]
+    #[test_case("break line 3", "line 1: Unknown breakpoint kind line" ; "kind")]
+    fn script_errors_work(script: &str, error: &str) {
+        assert_eq!(example().run_script(script), Err(error.to_string()));
+    }
+}
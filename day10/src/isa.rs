@@ -0,0 +1,118 @@
+use crate::cpu::Registers;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    X,
+    Y,
+}
+
+impl Register {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Register::X => "x",
+            Register::Y => "y",
+        }
+    }
+
+    pub fn get(&self, registers: &Registers) -> i32 {
+        match self {
+            Register::X => registers.x,
+            Register::Y => registers.y,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Immediate,
+    Register,
+    /// A label, or an absolute instruction index.
+    Target,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Opcode {
+    Noop,
+    AddX,
+    AddY,
+    Mul,
+    Jnz,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OpcodeInfo {
+    pub opcode: Opcode,
+    pub mnemonic: &'static str,
+    pub cycles: u32,
+    pub operands: &'static [Operand],
+}
+
+/// Every instruction the CPU understands.
+pub const INSTRUCTION_SET: [OpcodeInfo; 5] = [
+    OpcodeInfo {
+        opcode: Opcode::Noop,
+        mnemonic: "noop",
+        cycles: 1,
+        operands: &[],
+    },
+    OpcodeInfo {
+        opcode: Opcode::AddX,
+        mnemonic: "addx",
+        cycles: 2,
+        operands: &[Operand::Immediate],
+    },
+    OpcodeInfo {
+        opcode: Opcode::AddY,
+        mnemonic: "addy",
+        cycles: 2,
+        operands: &[Operand::Immediate],
+    },
+    // multiplies X
+    OpcodeInfo {
+        opcode: Opcode::Mul,
+        mnemonic: "mul",
+        cycles: 3,
+        operands: &[Operand::Immediate],
+    },
+    // jumps when the register is not zero
+    OpcodeInfo {
+        opcode: Opcode::Jnz,
+        mnemonic: "jnz",
+        cycles: 2,
+        operands: &[Operand::Register, Operand::Target],
+    },
+];
+
+impl Opcode {
+    pub fn info(&self) -> &'static OpcodeInfo {
+        INSTRUCTION_SET.iter().find(|i| i.opcode == *self).unwrap()
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        INSTRUCTION_SET
+            .iter()
+            .find(|i| i.mnemonic == mnemonic)
+            .map(|i| i.opcode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_set_is_complete() {
+        for opcode in [
+            Opcode::Noop,
+            Opcode::AddX,
+            Opcode::AddY,
+            Opcode::Mul,
+            Opcode::Jnz,
+        ] {
+            let info = opcode.info();
+            assert_eq!(Opcode::from_mnemonic(info.mnemonic), Some(opcode));
+            assert!(info.cycles > 0);
+        }
+        assert_eq!(Opcode::from_mnemonic("addz"), None);
+    }
+}
@@ -1,66 +1,95 @@
+use std::{collections::HashMap, fmt};
+
+pub mod asm;
+pub mod cpu;
+pub mod crt;
+pub mod debugger;
+pub mod isa;
+pub mod sampling;
+pub mod synth;
+pub mod vcd;
+
+use asm::ParseError;
+use isa::{Opcode, Register};
+use sampling::{sample, SamplingSchedule, SignalSum};
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Instruction {
     Noop,
     AddX(i32),
+    AddY(i32),
+    Mul(i32),
+    /// Jumps to the instruction at the given index when the register is not zero.
+    Jnz(Register, usize),
 }
 
-pub fn parse_instruction(line: &str) -> Result<Instruction, String> {
-    match line.splitn(2, ' ').collect::<Vec<_>>()[..] {
-        ["noop"] => Ok(Instruction::Noop),
-        ["addx", x] => Ok(Instruction::AddX(
-            x.parse::<i32>().map_err(|e| e.to_string())?,
-        )),
-        _ => Err("Unknown pattern".to_string()),
+impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::Noop => Opcode::Noop,
+            Instruction::AddX(_) => Opcode::AddX,
+            Instruction::AddY(_) => Opcode::AddY,
+            Instruction::Mul(_) => Opcode::Mul,
+            Instruction::Jnz(..) => Opcode::Jnz,
+        }
     }
-}
 
-struct Registry {
-    x: i32,
+    /// Number of cycles the instruction takes to complete.
+    pub fn cycles(&self) -> u32 {
+        self.opcode().info().cycles
+    }
 }
 
-pub fn interpretor<E: std::fmt::Display>(
-    mut lines: impl Iterator<Item = Result<String, E>>,
-) -> Result<i32, String> {
-    let mut registry = Registry { x: 1 };
-    let mut last_instruction: Option<Instruction> = None;
-    let mut signal: i32 = 0;
-    for cycle in 1.. {
-        // println!("\ncycle: {cycle}");
-        if cycle == 20 || (cycle + 20) % 40 == 0 {
-            // println!(" => calculating signal: {signal}");
-            signal += cycle * registry.x;
-        }
-        match last_instruction {
-            Some(instruction) => {
-                if let Instruction::AddX(x) = instruction {
-                    // println!("adding {x}");
-                    registry.x += x;
-                };
-                last_instruction = None;
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.opcode().info().mnemonic;
+        match self {
+            Instruction::Noop => write!(f, "{mnemonic}"),
+            Instruction::AddX(n) | Instruction::AddY(n) | Instruction::Mul(n) => {
+                write!(f, "{mnemonic} {n}")
             }
-            None => {
-                match lines.next() {
-                    Some(Ok(line)) => match parse_instruction(line.as_str())? {
-                        Instruction::Noop => {
-                            // println!("noop");
-                        }
-                        instruction => {
-                            // println!("setting multi cycle instruction");
-                            last_instruction = Some(instruction);
-                        }
-                    },
-                    Some(Err(e)) => return Err(e.to_string()),
-                    None => {
-                        // println!("finished all instructions");
-                        break;
-                    }
-                }
+            Instruction::Jnz(register, target) => {
+                write!(f, "{mnemonic} {} {target}", register.name())
             }
-        };
-        // println!("registry value: {}", registry.x);
+        }
     }
+}
 
-    Ok(signal)
+/// A single instruction, jump targets given as instruction indices. Use `asm::assemble` for
+/// whole programs with labels.
+pub fn parse_instruction(line: &str) -> Result<Instruction, String> {
+    asm::parse_line(line, &HashMap::new()).map_err(|e| e.to_string())
+}
+
+/// Sum of the puzzle's signal strengths, reading and running one line at a time. Stops at the
+/// first line that cannot be read or parsed, naming it. Jump targets must be instruction
+/// indices, as labels can point forward; see `interpret_assembly` for those.
+pub fn interpretor<E: std::fmt::Display>(
+    lines: impl Iterator<Item = Result<String, E>>,
+) -> Result<i64, String> {
+    let mut error = None;
+    let program = lines
+        .enumerate()
+        .map_while(|(i, line)| {
+            let parsed = match line {
+                Err(e) => Err(format!("line {}: {e}", i + 1)),
+                Ok(text) if text.trim().is_empty() => Ok(None),
+                Ok(text) => asm::parse_line(text.trim(), &HashMap::new())
+                    .map(Some)
+                    .map_err(|kind| ParseError { line: i + 1, kind }.to_string()),
+            };
+            parsed.map_err(|e| error = Some(e)).ok()
+        })
+        .flatten();
+    let total = sample(program, SamplingSchedule::puzzle(), SignalSum::default()).total;
+
+    error.map_or(Ok(total), Err)
+}
+
+/// `interpretor` for a whole program assembled with `asm::assemble`, labels included.
+pub fn interpret_assembly(source: &str) -> Result<i64, String> {
+    let program = asm::assemble(source).map_err(|e| e.to_string())?;
+    Ok(sample(program, SamplingSchedule::puzzle(), SignalSum::default()).total)
 }
 
 #[cfg(test)]
@@ -71,6 +100,9 @@ mod tests {
     #[test_case("noop", I::Noop)]
     #[test_case("addx 5", I::AddX(5))]
     #[test_case("addx -1",I::AddX(-1))]
+    #[test_case("addy 2", I::AddY(2))]
+    #[test_case("mul -3", I::Mul(-3))]
+    #[test_case("jnz y 7", I::Jnz(Register::Y, 7))]
     fn instruction_parser_successes(line: &str, expect: I) {
         assert_eq!(parse_instruction(line).unwrap(), expect);
     }
@@ -81,10 +113,42 @@ mod tests {
     #[test_case("addx abc 123")]
     #[test_case("addx 123 123")]
     #[test_case("addx abc asd")]
+    #[test_case("jnz x")]
+    #[test_case("jnz x loop")]
+    #[test_case("")]
     fn instruction_parser_failures(line: &str) {
         assert!(parse_instruction(line).is_err());
     }
 
+    #[test]
+    fn interpretor_stops_at_the_first_error() {
+        let lines = ["noop", "addx 1", "jump"]
+            .into_iter()
+            .map(|line| Ok::<_, String>(line.to_string()))
+            .chain(std::iter::once(Err("unreadable".to_string())))
+            .chain(std::iter::repeat_with(|| panic!("read past the error")));
+        assert_eq!(
+            interpretor(lines),
+            Err("line 3: unknown instruction 'jump'".to_string())
+        );
+
+        let lines = ["noop", ""]
+            .into_iter()
+            .map(|line| Ok(line.to_string()))
+            .chain(std::iter::once(Err("unreadable")));
+        assert_eq!(interpretor(lines), Err("line 3: unreadable".to_string()));
+    }
+
+    #[test]
+    fn interpret_assembly_supports_labels() {
+        let source = format!("jnz x skip\naddx 1000\nskip:\n{}", "noop\n".repeat(20));
+        assert_eq!(interpret_assembly(&source), Ok(20));
+        assert_eq!(
+            interpret_assembly("jnz x nowhere"),
+            Err("line 1: unknown label 'nowhere'".to_string())
+        );
+    }
+
     mod part1 {
         use std::{
             fs::File,
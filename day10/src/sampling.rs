@@ -0,0 +1,174 @@
+use crate::{
+    cpu::{Cpu, Observer, Tick},
+    Instruction,
+};
+
+/// Which cycles an observer gets to see.
+#[derive(Clone, Debug)]
+pub enum SamplingSchedule {
+    Cycles(Vec<usize>),
+    /// `start`, `start + step`, `start + 2 * step`, ...; a `step` of 0 samples `start` only.
+    Progression {
+        start: usize,
+        step: usize,
+    },
+    Predicate(fn(usize) -> bool),
+}
+
+impl SamplingSchedule {
+    /// Cycles 20, 60, 100, ... as in the puzzle.
+    pub fn puzzle() -> Self {
+        SamplingSchedule::Progression {
+            start: 20,
+            step: 40,
+        }
+    }
+
+    pub fn contains(&self, cycle: usize) -> bool {
+        match self {
+            SamplingSchedule::Cycles(cycles) => cycles.contains(&cycle),
+            SamplingSchedule::Progression { start, step: 0 } => cycle == *start,
+            SamplingSchedule::Progression { start, step } => {
+                cycle >= *start && (cycle - start).is_multiple_of(*step)
+            }
+            SamplingSchedule::Predicate(f) => f(cycle),
+        }
+    }
+}
+
+/// Passes the ticks on the schedule on to another observer.
+#[derive(Clone, Debug)]
+pub struct Sampled<O> {
+    pub schedule: SamplingSchedule,
+    pub observer: O,
+}
+
+impl<O: Observer> Observer for Sampled<O> {
+    fn tick(&mut self, tick: &Tick) {
+        if self.schedule.contains(tick.cycle) {
+            self.observer.tick(tick);
+        }
+    }
+}
+
+/// `cycle * X`, widened so it cannot overflow for any X.
+pub fn signal_strength(tick: &Tick) -> i64 {
+    (tick.cycle as i64).wrapping_mul(i64::from(tick.registers.x))
+}
+
+/// Sum of the signal strength, `cycle * X`, of every tick seen. Wraps around on overflow, like
+/// the registers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SignalSum {
+    pub total: i64,
+}
+
+impl Observer for SignalSum {
+    fn tick(&mut self, tick: &Tick) {
+        self.total = self.total.wrapping_add(signal_strength(tick));
+    }
+}
+
+/// The tick with the strongest signal, the first one on ties.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaxSignal {
+    pub best: Option<Tick>,
+}
+
+impl Observer for MaxSignal {
+    fn tick(&mut self, tick: &Tick) {
+        if self
+            .best
+            .is_none_or(|best| signal_strength(tick) > signal_strength(&best))
+        {
+            self.best = Some(*tick);
+        }
+    }
+}
+
+/// Runs `program` to the end, showing `observer` the cycles on the schedule.
+pub fn sample<O: Observer>(
+    program: impl IntoIterator<Item = Instruction>,
+    schedule: SamplingSchedule,
+    observer: O,
+) -> O {
+    let mut sampled = Sampled { schedule, observer };
+    Cpu::new(program).run(&mut sampled);
+    sampled.observer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asm::assemble, cpu::Trace};
+    use test_case::test_case;
+
+    fn example() -> Vec<Instruction> {
+        assemble(include_str!("../example")).unwrap()
+    }
+
+    #[test_case(SamplingSchedule::puzzle(), &[20, 60, 100] ; "puzzle")]
+    #[test_case(SamplingSchedule::Cycles(vec![3, 1]), &[1, 3] ; "cycles")]
+    #[test_case(SamplingSchedule::Progression { start: 2, step: 0 }, &[2] ; "single")]
+    #[test_case(SamplingSchedule::Predicate(|c| c % 50 == 0), &[50, 100] ; "predicate")]
+    fn schedules_work(schedule: SamplingSchedule, expected: &[usize]) {
+        assert_eq!(
+            (1..=100)
+                .filter(|&c| schedule.contains(c))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn aggregators_work() {
+        let sum = sample(example(), SamplingSchedule::puzzle(), SignalSum::default());
+        assert_eq!(sum.total, 13140);
+
+        let max = sample(example(), SamplingSchedule::puzzle(), MaxSignal::default());
+        let best = max.best.unwrap();
+        assert_eq!((best.cycle, signal_strength(&best)), (220, 3960));
+
+        let trace = sample(
+            example(),
+            SamplingSchedule::Cycles(vec![20, 60, 300]),
+            Trace::default(),
+        );
+        assert_eq!(
+            trace
+                .0
+                .iter()
+                .map(|t| (t.cycle, t.registers.x))
+                .collect::<Vec<_>>(),
+            vec![(20, 21), (60, 19)]
+        );
+    }
+
+    #[test]
+    fn large_registers_do_not_overflow() {
+        let program = assemble(&format!("mul 100000\nmul 100000\n{}", "noop\n".repeat(20)));
+        let sum = sample(
+            program.unwrap(),
+            SamplingSchedule::puzzle(),
+            SignalSum::default(),
+        );
+        // X wrapped to 10^10 - 2 * 2^32 by cycle 20
+        assert_eq!(sum.total, 20 * 1410065408);
+    }
+
+    #[test]
+    fn any_iterator_works() {
+        // ten `addx 1`, never collected into a program
+        let program = std::iter::repeat_n(Instruction::AddX(1), 10);
+        let sum = sample(
+            program,
+            SamplingSchedule::Progression { start: 1, step: 2 },
+            SignalSum::default(),
+        );
+        // X is k + 1 during cycles 2k + 1 and 2k + 2
+        assert_eq!(
+            sum.total,
+            (0..10).map(|k| (2 * k + 1) * (k + 1)).sum::<i64>()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    crt::{Crt, HEIGHT, WIDTH},
+    Instruction,
+};
+
+const PIXELS: usize = WIDTH * HEIGHT;
+/// Values of X worth considering: every lit pixel needs X within one of its column, and `-2` and
+/// `42` keep a whole row dark, so nothing further out is ever needed.
+const X_VALUES: RangeInclusive<i32> = -2..=WIDTH as i32 + 2;
+
+fn lit(target: &Crt, pixel: usize) -> bool {
+    target.pixels[pixel / WIDTH][pixel % WIDTH]
+}
+
+/// Whether the beam draws `pixel` as in the target while X has the given value.
+fn allowed(target: &Crt, pixel: usize, x: i32) -> bool {
+    lit(target, pixel) == ((x - (pixel % WIDTH) as i32).abs() <= 1)
+}
+
+fn index(x: i32) -> usize {
+    (x - X_VALUES.start()) as usize
+}
+
+/// X values ordered by distance from `x`, so the smallest `addx` wins ties.
+fn targets(x: i32) -> impl Iterator<Item = i32> {
+    let mut xs = X_VALUES.collect::<Vec<_>>();
+    xs.sort_by_key(|&t| ((t - x).abs(), t));
+    xs.into_iter()
+}
+
+/// The shortest `noop`/`addx` program whose CRT output is `target`, drawing each pixel once.
+///
+/// Instructions can stop before the last pixel when everything after them is dark. X only
+/// changes at the end of an `addx`, which holds it for two cycles, so not every image can be
+/// drawn; the error names the first pixel no program can reach.
+pub fn synthesize(target: &Crt) -> Result<Vec<Instruction>, String> {
+    let mut dark_from = vec![true; PIXELS + 1];
+    for pixel in (0..PIXELS).rev() {
+        dark_from[pixel] = dark_from[pixel + 1] && !lit(target, pixel);
+    }
+
+    // fewest instructions to draw everything from a pixel on with X at a value, and the first one
+    let mut best = vec![vec![(usize::MAX, None); X_VALUES.count()]; PIXELS + 2];
+    for pixel in (0..=PIXELS).rev() {
+        for x in X_VALUES {
+            if dark_from[pixel] {
+                best[pixel][index(x)] = (0, None);
+                continue;
+            }
+            if !allowed(target, pixel, x) {
+                continue;
+            }
+            let mut choice = match best[pixel + 1][index(x)] {
+                (cost, _) if cost != usize::MAX => (cost + 1, Some(Instruction::Noop)),
+                _ => (usize::MAX, None),
+            };
+            if pixel + 1 < PIXELS && allowed(target, pixel + 1, x) {
+                for next in targets(x) {
+                    let (cost, _) = best[pixel + 2][index(next)];
+                    if cost != usize::MAX && cost + 1 < choice.0 {
+                        choice = (cost + 1, Some(Instruction::AddX(next - x)));
+                    }
+                }
+            }
+            best[pixel][index(x)] = choice;
+        }
+    }
+
+    let (mut pixel, mut x) = (0, 1);
+    if best[pixel][index(x)].0 == usize::MAX {
+        return Err(unreachable_pixel(target));
+    }
+    let mut program = vec![];
+    while let (_, Some(instruction)) = best[pixel][index(x)] {
+        program.push(instruction);
+        pixel += instruction.cycles() as usize;
+        if let Instruction::AddX(v) = instruction {
+            x += v;
+        }
+    }
+    Ok(program)
+}
+
+/// The furthest pixel any sequence of instructions gets to, which must be the one that cannot
+/// be drawn.
+fn unreachable_pixel(target: &Crt) -> String {
+    let mut reached = vec![vec![false; X_VALUES.count()]; PIXELS + 2];
+    reached[0][index(1)] = true;
+    let mut furthest = 0;
+    for pixel in 0..PIXELS {
+        for x in X_VALUES {
+            if !reached[pixel][index(x)] || !allowed(target, pixel, x) {
+                continue;
+            }
+            furthest = furthest.max(pixel + 1);
+            reached[pixel + 1][index(x)] = true;
+            if pixel + 1 < PIXELS && allowed(target, pixel + 1, x) {
+                furthest = furthest.max(pixel + 2);
+                X_VALUES.for_each(|next| reached[pixel + 2][index(next)] = true);
+            }
+        }
+    }
+    format!(
+        "Pixel at row {}, column {} cannot be drawn",
+        furthest / WIDTH,
+        furthest % WIDTH
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asm::assemble, cpu::Cpu};
+
+    fn render(program: Vec<Instruction>) -> Crt {
+        let mut crt = Crt::default();
+        Cpu::new(program).run(&mut crt);
+        crt
+    }
+
+    #[test]
+    fn example_round_trips() {
+        let example = assemble(include_str!("../example")).unwrap();
+        let target = render(example.clone());
+        let program = synthesize(&target).unwrap();
+
+        assert_eq!(render(program.clone()), target);
+        assert!(program.len() < example.len());
+    }
+
+    #[test]
+    fn input_round_trips() {
+        let input = assemble(&std::fs::read_to_string("input").unwrap()).unwrap();
+        let target = render(input);
+        let program = synthesize(&target).unwrap();
+
+        assert_eq!(render(program).decode().unwrap(), "RKPJBPLA");
+    }
+
+    #[test]
+    fn letters_round_trip() {
+        // X is 1 for the first two cycles, so only letters starting with two lit pixels can
+        // come first
+        for letters in ["EFZ", "RLUSE", "ZHUL"] {
+            let target = Crt::from_letters(letters).unwrap();
+            let program = synthesize(&target).unwrap();
+            assert_eq!(render(program).decode().unwrap().trim_end(), letters);
+        }
+    }
+
+    #[test]
+    fn shortest_programs_are_found() {
+        assert_eq!(synthesize(&Crt::default()).unwrap(), vec![]);
+
+        // X starts under the first three pixels, and the rest is left undrawn
+        let mut target = Crt::default();
+        target.pixels[0][..3].fill(true);
+        assert_eq!(
+            synthesize(&target).unwrap(),
+            vec![Instruction::Noop, Instruction::AddX(0)]
+        );
+    }
+
+    #[test]
+    fn impossible_images_fail() {
+        // X is still 1 during the second cycle, which lights the second pixel
+        let mut target = Crt::default();
+        target.pixels[0][0] = true;
+        target.pixels[0][2] = true;
+        assert_eq!(
+            synthesize(&target),
+            Err("Pixel at row 0, column 1 cannot be drawn".to_string())
+        );
+    }
+}
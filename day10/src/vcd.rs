@@ -0,0 +1,103 @@
+use std::fmt::Write as _;
+
+use crate::{
+    cpu::{Observer, Tick},
+    crt::beam,
+};
+
+/// Identifiers of the dumped signals, in the order they are declared.
+const SIGNALS: [(&str, &str, usize, &str); 5] = [
+    ("integer", "!", 32, "x"),
+    ("integer", "\"", 32, "pc"),
+    ("string", "#", 1, "instruction"),
+    ("integer", "$", 8, "beam_row"),
+    ("integer", "%", 8, "beam_col"),
+];
+
+/// Writes every cycle as a Value Change Dump, one time unit per cycle, for viewers such as
+/// GTKWave. The instruction is dumped as a string signal with spaces replaced by `_`.
+#[derive(Clone, Debug)]
+pub struct Vcd {
+    out: String,
+    last: Option<[String; 5]>,
+    cycle: usize,
+}
+
+impl Default for Vcd {
+    fn default() -> Self {
+        let mut out = String::from("$version day10 cpu $end\n$timescale 1ns $end\n");
+        out += "$scope module cpu $end\n";
+        for (kind, id, width, name) in SIGNALS {
+            writeln!(out, "$var {kind} {width} {id} {name} $end").unwrap();
+        }
+        out += "$upscope $end\n$enddefinitions $end\n";
+        Vcd {
+            out,
+            last: None,
+            cycle: 0,
+        }
+    }
+}
+
+impl Observer for Vcd {
+    fn tick(&mut self, tick: &Tick) {
+        let (row, col) = beam(tick.cycle);
+        let values = [
+            format!("b{:b}", tick.registers.x),
+            format!("b{:b}", tick.pc),
+            format!("s{}", tick.instruction.to_string().replace(' ', "_")),
+            format!("b{row:b}"),
+            format!("b{col:b}"),
+        ];
+
+        writeln!(self.out, "#{}", tick.cycle).unwrap();
+        for (i, value) in values.iter().enumerate() {
+            if self.last.as_ref().is_none_or(|last| last[i] != *value) {
+                writeln!(self.out, "{value} {}", SIGNALS[i].1).unwrap();
+            }
+        }
+        self.last = Some(values);
+        self.cycle = tick.cycle;
+    }
+}
+
+impl Vcd {
+    /// The dump, with a final timestamp closing the last cycle.
+    pub fn finish(mut self) -> String {
+        writeln!(self.out, "#{}", self.cycle + 1).unwrap();
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asm::assemble, cpu::Cpu};
+
+    #[test]
+    fn vcd_works() {
+        let mut vcd = Vcd::default();
+        Cpu::new(assemble("noop\naddx -3").unwrap()).run(&mut vcd);
+        let dump = vcd.finish();
+
+        let (header, changes) = dump.split_once("$enddefinitions $end\n").unwrap();
+        assert!(header.contains("$var integer 32 ! x $end\n"));
+        assert!(header.contains("$var string 1 # instruction $end\n"));
+        assert_eq!(
+            changes,
+            "#1\nb1 !\nb0 \"\nsnoop #\nb0 $\nb0 %\n\
+             #2\nb1 \"\nsaddx_-3 #\nb1 %\n\
+             #3\nb10 %\n\
+             #4\n"
+        );
+    }
+
+    #[test]
+    fn negative_values_are_twos_complement() {
+        let mut vcd = Vcd::default();
+        Cpu::new(assemble("addx -3\nnoop").unwrap()).run(&mut vcd);
+        assert!(vcd
+            .finish()
+            .contains(&format!("#3\nb{} !\n", "1".repeat(31) + "0")));
+    }
+}
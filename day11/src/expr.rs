@@ -0,0 +1,188 @@
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, one_of, space0},
+    multi::fold_many0,
+    sequence::{delimited, pair},
+    *,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    fn symbol(&self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Sub => '-',
+            BinOp::Mul => '*',
+            BinOp::Div => '/',
+            BinOp::Rem => '%',
+        }
+    }
+
+    fn from_symbol(c: char) -> BinOp {
+        match c {
+            '+' => BinOp::Add,
+            '-' => BinOp::Sub,
+            '*' => BinOp::Mul,
+            '/' => BinOp::Div,
+            _ => BinOp::Rem,
+        }
+    }
+
+    pub fn apply(&self, a: u64, b: u64) -> Result<u64, EvalError> {
+        if b == 0 && matches!(self, BinOp::Div | BinOp::Rem) {
+            return Err(EvalError::DivisionByZero);
+        }
+        match self {
+            BinOp::Add => a.checked_add(b),
+            BinOp::Sub => a.checked_sub(b),
+            BinOp::Mul => a.checked_mul(b),
+            BinOp::Div => a.checked_div(b),
+            BinOp::Rem => a.checked_rem(b),
+        }
+        .ok_or(EvalError::Overflow(*self, a, b))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    /// The result does not fit a `u64`, negative results included.
+    Overflow(BinOp, u64, u64),
+    DivisionByZero,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Overflow(op, a, b) => write!(f, "{a} {} {b} overflows", op.symbol()),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+/// The right hand side of a monkey's operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Old,
+    Literal(u64),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, old: u64) -> Result<u64, EvalError> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Literal(x) => Ok(*x),
+            Expr::Binary(op, a, b) => op.apply(a.eval(old)?, b.eval(old)?),
+        }
+    }
+}
+
+fn factor(input: &str) -> IResult<&str, Expr> {
+    delimited(
+        space0,
+        alt((
+            tag("old").map(|_| Expr::Old),
+            character::complete::u64.map(Expr::Literal),
+            delimited(char('('), expression, pair(space0, char(')'))),
+        )),
+        space0,
+    )(input)
+}
+
+/// Left associative chain of `operand`s joined by any of the `operators`.
+fn chain<'a>(
+    operand: fn(&'a str) -> IResult<&'a str, Expr>,
+    operators: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Expr> {
+    move |input| {
+        let (input, first) = operand(input)?;
+        fold_many0(
+            pair(one_of(operators), operand),
+            move || first.clone(),
+            |a, (op, b)| Expr::Binary(BinOp::from_symbol(op), Box::new(a), Box::new(b)),
+        )(input)
+    }
+}
+
+fn term(input: &str) -> IResult<&str, Expr> {
+    chain(factor, "*/%")(input)
+}
+
+/// `old`, unsigned literals, `+ - * / %` and parentheses, with the usual precedence.
+pub fn expression(input: &str) -> IResult<&str, Expr> {
+    chain(term, "+-")(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn eval(input: &str, old: u64) -> Result<u64, EvalError> {
+        let (rest, expr) = expression(input).unwrap();
+        assert_eq!(rest, "");
+        expr.eval(old)
+    }
+
+    #[test_case("old * 19", 3, Ok(57) ; "mul")]
+    #[test_case("old * old", 3, Ok(9) ; "square")]
+    #[test_case("1 + 2 * old", 3, Ok(7) ; "precedence")]
+    #[test_case("(1 + 2) * old", 3, Ok(9) ; "parentheses")]
+    #[test_case("old - 2 - 1", 10, Ok(7) ; "left associative")]
+    #[test_case("old / 2 % 3", 10, Ok(2) ; "div rem")]
+    #[test_case("( ( old ) )", 10, Ok(10) ; "nested")]
+    #[test_case("old - 11", 10, Err(EvalError::Overflow(BinOp::Sub, 10, 11)) ; "negative")]
+    #[test_case("old * old", u64::MAX, Err(EvalError::Overflow(BinOp::Mul, u64::MAX, u64::MAX)) ; "overflow")]
+    #[test_case("old % (old - 10)", 10, Err(EvalError::DivisionByZero) ; "division by zero")]
+    fn eval_works(input: &str, old: u64, expected: Result<u64, EvalError>) {
+        assert_eq!(eval(input, old), expected);
+    }
+
+    #[test]
+    fn tree_is_built() {
+        let (_, expr) = expression("old + 2 * (old - 1)").unwrap();
+        assert_eq!(
+            expr,
+            Expr::Binary(
+                BinOp::Add,
+                Box::new(Expr::Old),
+                Box::new(Expr::Binary(
+                    BinOp::Mul,
+                    Box::new(Expr::Literal(2)),
+                    Box::new(Expr::Binary(
+                        BinOp::Sub,
+                        Box::new(Expr::Old),
+                        Box::new(Expr::Literal(1))
+                    ))
+                ))
+            )
+        );
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("* 2" ; "missing operand")]
+    #[test_case("(old + 1" ; "unclosed")]
+    #[test_case("new" ; "unknown name")]
+    fn invalid_expressions_fail(input: &str) {
+        assert!(expression(input).is_err());
+    }
+
+    #[test]
+    fn trailing_input_is_left() {
+        assert_eq!(
+            expression("old +\n  Test").unwrap(),
+            ("+\n  Test", Expr::Old)
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use expr::{expression, BinOp, EvalError, Expr};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -9,6 +10,8 @@ use nom::{
     *,
 };
 
+pub mod expr;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum MonkeyTest {
     Mod(u64),
@@ -27,18 +30,36 @@ pub enum MonkeyOperation {
     Add(u64),
     Square,
     Mul(u64),
+    Expr(Expr),
 }
 
 impl MonkeyOperation {
-    pub fn apply(&self, old: u64) -> u64 {
+    pub fn apply(&self, old: u64) -> Result<u64, EvalError> {
         match self {
-            MonkeyOperation::Add(x) => old + x,
-            MonkeyOperation::Square => old * old,
-            MonkeyOperation::Mul(x) => old * x,
+            MonkeyOperation::Add(x) => BinOp::Add.apply(old, *x),
+            MonkeyOperation::Square => BinOp::Mul.apply(old, old),
+            MonkeyOperation::Mul(x) => BinOp::Mul.apply(old, *x),
+            MonkeyOperation::Expr(expr) => expr.eval(old),
         }
     }
 }
 
+/// Keeps the puzzle's own forms as their shortcuts, anything else as the whole tree.
+impl From<Expr> for MonkeyOperation {
+    fn from(expr: Expr) -> Self {
+        let shortcut = match &expr {
+            Expr::Binary(op, a, b) => match (op, &**a, &**b) {
+                (BinOp::Mul, Expr::Old, Expr::Old) => Some(MonkeyOperation::Square),
+                (BinOp::Mul, Expr::Old, Expr::Literal(x)) => Some(MonkeyOperation::Mul(*x)),
+                (BinOp::Add, Expr::Old, Expr::Literal(x)) => Some(MonkeyOperation::Add(*x)),
+                _ => None,
+            },
+            _ => None,
+        };
+        shortcut.unwrap_or(MonkeyOperation::Expr(expr))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Monkey {
     pub id: u64,
@@ -51,14 +72,9 @@ pub struct Monkey {
 }
 
 fn operation(input: &str) -> IResult<&str, MonkeyOperation> {
-    let (input, _) = tag("Operation: new = old ")(input)?;
-    let (input, operation) = alt((
-        tag("* old").map(|_| MonkeyOperation::Square),
-        preceded(tag("* "), character::complete::u64).map(MonkeyOperation::Mul),
-        preceded(tag("+ "), character::complete::u64).map(MonkeyOperation::Add),
-    ))(input)?;
-
-    Ok((input, operation))
+    preceded(tag("Operation: new = "), expression)
+        .map(MonkeyOperation::from)
+        .parse(input)
 }
 
 fn header(input: &str) -> IResult<&str, u64> {
@@ -135,7 +151,12 @@ pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, String> {
 //     let mut transfers = vec![];
 // }
 
-pub fn monkey_keep_away<W>(mut monkeys: Vec<Monkey>, worry_fn: W, rounds: usize) -> Vec<Monkey>
+/// Fails on the first operation that overflows or divides by zero.
+pub fn monkey_keep_away<W>(
+    mut monkeys: Vec<Monkey>,
+    worry_fn: W,
+    rounds: usize,
+) -> Result<Vec<Monkey>, EvalError>
 where
     W: Fn(u64) -> u64,
 {
@@ -151,8 +172,8 @@ where
             if round % 1000 == 0 || round == 20 || round == 1 {
                 println!("Monkey {} inspected items {} times.", cm.id, cm.inspections);
             }
-            cm.starting_items.drain(0..).for_each(|old| {
-                let new = worry_fn(cm.operation.apply(old));
+            for old in cm.starting_items.drain(0..) {
+                let new = worry_fn(cm.operation.apply(old)?);
                 let dm = if cm.test.apply(new) {
                     cm.test_yay
                 } else {
@@ -160,10 +181,10 @@ where
                 };
                 let bag = transfers.get_mut(&dm).unwrap();
                 bag.push(new);
-            });
+            }
         }
     }
-    monkeys
+    Ok(monkeys)
 }
 
 #[cfg(test)]
@@ -208,6 +229,34 @@ mod tests {
         };
     }
 
+    #[test_case("Operation: new = (old + 3) * 2", 5, 16 ; "parentheses")]
+    #[test_case("Operation: new = 2 * old", 5, 10 ; "literal first")]
+    #[test_case("Operation: new = old * old % 7 - 1", 5, 3 ; "precedence")]
+    fn expression_operation_parser_works(input: &str, old: u64, expected: u64) {
+        let (_, actual) = operation(input).unwrap();
+        match actual {
+            MonkeyOperation::Expr(_) => assert_eq!(actual.apply(old), Ok(expected)),
+            _ => panic!("This should be an expression"),
+        };
+    }
+
+    #[test]
+    fn overflow_fails() {
+        let input = "Monkey 0:
+  Starting items: 2
+  Operation: new = old * old * old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0";
+
+        let v = parse_monkeys(input).unwrap();
+        // 2, 2^3, 2^9, 2^27 and then 2^27 cubed
+        assert_eq!(
+            monkey_keep_away(v, |x| x, 4).unwrap_err(),
+            EvalError::Overflow(BinOp::Mul, 1 << 54, 1 << 27)
+        );
+    }
+
     #[test_case("Test: divisible by 13", 13)]
     fn test_divisible(input: &str, expected: u64) {
         let (_, actual) = test_parser(input).unwrap();
@@ -245,7 +294,7 @@ mod tests {
             let input = include_str!("../example");
             let v = parse_monkeys(input).unwrap();
             assert_eq!(v.len(), 4);
-            let mut v = monkey_keep_away(v, |v| v / 3, 20);
+            let mut v = monkey_keep_away(v, |v| v / 3, 20).unwrap();
             v.sort_by(|m1, m2| m1.inspections.cmp(&m2.inspections));
             v.reverse();
             let p = v[0..=1].iter().map(|m| m.inspections).product::<usize>();
@@ -257,7 +306,7 @@ mod tests {
             let input = include_str!("../input");
             let v = parse_monkeys(input).unwrap();
             assert_eq!(v.len(), 8);
-            let mut v = monkey_keep_away(v, |v| v / 3, 20);
+            let mut v = monkey_keep_away(v, |v| v / 3, 20).unwrap();
             v.sort_by(|m1, m2| m1.inspections.cmp(&m2.inspections));
             v.reverse();
             let p = v[0..=1].iter().map(|m| m.inspections).product::<usize>();
@@ -275,7 +324,7 @@ mod tests {
                 MonkeyTest::Mod(x) => x,
             }).product();
             assert_eq!(v.len(), 4);
-            let mut v = monkey_keep_away(v, |x| x % magic, 10_000);
+            let mut v = monkey_keep_away(v, |x| x % magic, 10_000).unwrap();
             v.sort_by(|m1, m2| m1.inspections.cmp(&m2.inspections));
             v.reverse();
             let p = v[0..=1].iter().map(|m| m.inspections).product::<usize>();
@@ -290,7 +339,7 @@ mod tests {
                 MonkeyTest::Mod(x) => x,
             }).product();
             assert_eq!(v.len(), 8);
-            let mut v = monkey_keep_away(v, |x| x % magic, 10_000);
+            let mut v = monkey_keep_away(v, |x| x % magic, 10_000).unwrap();
             v.sort_by(|m1, m2| m1.inspections.cmp(&m2.inspections));
             v.reverse();
             let p = v[0..=1].iter().map(|m| m.inspections).product::<usize>();